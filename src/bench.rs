@@ -0,0 +1,234 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+use crate::config;
+use crate::sudoku::{self, Sudoku};
+
+// `sudoku bench` times solving, counting solutions (up to 2) and rating every
+// puzzle of the bundled sets and of the puzzle files of the tests, one puzzle
+// at a time and each set over and over for at least LEAST, and prints how many
+// puzzles a second each one gets through as
+//
+//     <set> <operation> = <puzzles a second>
+//
+// lines. With a file of lines like those from an earlier run, `sudoku bench
+// <file>` also says how they compare, and fails when any gets through less
+// than MIN_RATIO times as many as the earlier one.
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Sudoku(#[from] sudoku::Error),
+    #[error("I/O Error")]
+    Io(#[from] std::io::Error),
+    #[error("Line {line}: expected '<set> <operation> = <puzzles a second>'")]
+    InvalidBaseline { line: usize },
+    #[error("Slower than the baseline: {}", .0.join(", "))]
+    Regression(Vec<String>)
+}
+
+// Sets of a puzzle a line
+const SETS: [(&str, &str); 3] = [
+    ("easy", "tests/bench/easy"),
+    ("seventeen", "tests/bench/seventeen"),
+    ("hardest", "tests/bench/hardest")
+];
+
+// Puzzle files with variants, each a set of its own
+const FILES: [&str; 10] = [
+    "tests/example",
+    "tests/example2",
+    "tests/jigsaw",
+    "tests/killer",
+    "tests/diagonal",
+    "tests/windoku",
+    "tests/thermo",
+    "tests/arrow",
+    "tests/kropki",
+    "tests/outside"
+];
+
+// Every operation goes over its set again until it has taken this long, so
+// sets of a single quick puzzle are measured over many runs and not just one
+// that the scheduler can make look slow
+const LEAST: Duration = Duration::from_millis(200);
+
+// A run with a lower ratio of its rate to the one of the baseline is a
+// regression
+const MIN_RATIO: f64 = 0.8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operation {
+    Solve,
+    Count,
+    Rate
+}
+
+impl Operation {
+    const ALL: [Operation; 3] = [Operation::Solve, Operation::Count, Operation::Rate];
+
+    fn name(self) -> &'static str {
+        match self {
+            Operation::Solve => "solve",
+            Operation::Count => "count",
+            Operation::Rate => "rate"
+        }
+    }
+
+    fn run(self, puzzle: &Sudoku) {
+        match self {
+            Operation::Solve => {
+                puzzle.solve();
+            }
+            Operation::Count => {
+                puzzle.count_solutions(2);
+            }
+            // like `sudoku batch --rate`, which only rates puzzles with a
+            // solution, and needs to know whether it's the only one
+            Operation::Rate => {
+                let count = puzzle.count_solutions(2);
+
+                if count > 0 {
+                    puzzle.rate(count == 1);
+                }
+            }
+        }
+    }
+}
+
+pub fn run(baseline: Option<&Path>) -> Result<(), Error> {
+    let mut sets = Vec::new();
+
+    for (name, path) in SETS {
+        sets.push((name.to_string(), sudoku::read_puzzles(Path::new(path))?));
+    }
+
+    for path in FILES {
+        let name = path.trim_start_matches("tests/").to_string();
+        sets.push((name, vec![Sudoku::from_file(Path::new(path))?]));
+    }
+
+    let results = measure(&sets);
+
+    for (key, rate) in &results {
+        println!("{} = {:.1}", key, rate);
+    }
+
+    if let Some(path) = baseline {
+        let baseline = parse_baseline(&std::fs::read_to_string(path)?)?;
+        let slower = compare(&results, &baseline);
+
+        if !slower.is_empty() {
+            return Err(Error::Regression(slower));
+        }
+    }
+
+    Ok(())
+}
+
+// Puzzles a second of every operation on every set, keyed by
+// "<set> <operation>"
+fn measure(sets: &[(String, Vec<Sudoku>)]) -> Vec<(String, f64)> {
+    let mut results = Vec::new();
+
+    for (name, puzzles) in sets {
+        for operation in Operation::ALL {
+            let now = Instant::now();
+            let mut runs = 0;
+
+            while runs == 0 || now.elapsed() < LEAST {
+                for puzzle in puzzles {
+                    operation.run(puzzle);
+                }
+
+                runs += 1;
+            }
+
+            let elapsed = now.elapsed();
+            let done = runs * puzzles.len();
+            let key = format!("{} {}", name, operation.name());
+            results.push((key, done as f64 / elapsed.as_secs_f64()));
+        }
+    }
+
+    results
+}
+
+fn parse_baseline(text: &str) -> Result<Vec<(String, f64)>, Error> {
+    config::entries(text)
+        .map(|entry| {
+            let (line, key, value) = entry.map_err(|line| Error::InvalidBaseline { line })?;
+            let rate = value.parse().map_err(|_| Error::InvalidBaseline { line })?;
+
+            Ok((key.to_string(), rate))
+        })
+        .collect()
+}
+
+// Prints how every result compares to the baseline, and returns the ones that
+// got too slow. Results missing from the baseline are new, and left alone.
+fn compare(results: &[(String, f64)], baseline: &[(String, f64)]) -> Vec<String> {
+    let mut slower = Vec::new();
+
+    for (key, rate) in results {
+        let Some((_, before)) = baseline.iter().find(|(other, _)| other == key) else {
+            continue;
+        };
+
+        let ratio = rate / before;
+        eprintln!("{}: {:.2}x", key, ratio);
+
+        if ratio < MIN_RATIO {
+            slower.push(key.clone());
+        }
+    }
+
+    slower
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn baseline_comparison() -> Result<(), anyhow::Error> {
+        let baseline = parse_baseline("# earlier\neasy solve = 100.0\neasy rate = 10\n")?;
+        assert_eq!(
+            baseline,
+            vec![("easy solve".to_string(), 100.0), ("easy rate".to_string(), 10.0)]
+        );
+        assert!(matches!(
+            parse_baseline("easy solve = fast"),
+            Err(Error::InvalidBaseline { line: 1 })
+        ));
+
+        let results = vec![
+            ("easy solve".to_string(), 90.0),
+            ("easy rate".to_string(), 7.5),
+            ("hardest solve".to_string(), 1.0)
+        ];
+        assert_eq!(compare(&results, &baseline), vec!["easy rate".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn bundled_sets() -> Result<(), anyhow::Error> {
+        for (_, path) in SETS {
+            let puzzles = sudoku::read_puzzles(Path::new(path))?;
+            assert!(!puzzles.is_empty());
+        }
+
+        let seventeen = sudoku::read_puzzles(Path::new("tests/bench/seventeen"))?;
+        let givens = |puzzle: &Sudoku| {
+            puzzle.iterate().filter(|(_, _, n)| n.value().is_some()).count()
+        };
+        assert!(seventeen.iter().all(|puzzle| givens(puzzle) == 17));
+
+        let results = measure(&[("seventeen".to_string(), seventeen[..1].to_vec())]);
+        let keys: Vec<&str> = results.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["seventeen solve", "seventeen count", "seventeen rate"]);
+        assert!(results.iter().all(|&(_, rate)| rate > 0.0));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use std::path::Path;
+use crate::bench;
+use crate::sudoku::{self, Mode, Rating, Report, Sudoku};
+
+// With arguments the game works without a window:
+//
+//     sudoku solve <file>
+//     sudoku rate <file>
+//
+// go through a file with a puzzle a line, on every core, and print a line for
+// each puzzle in the same order, with tabs between
+//
+//     <solution, or -> <solutions, up to 2> <rating, or -> <milliseconds>
+//
+// with no rating when solving, or for puzzles without a solution,
+// and `sudoku bench [<baseline>]` times the solver (see bench.rs).
+//
+//     sudoku cnf <puzzle>
+//     sudoku model <puzzle> <model>
+//
+// print a puzzle file (with its constraints) as DIMACS CNF for a SAT solver,
+// and the board from the model the solver found for it, as one line, to check
+// against `solve`.
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Usage: sudoku [solve|rate <file> | bench [<baseline>] | cnf <puzzle> | model <puzzle> <model>]")]
+    Usage,
+    #[error("The model breaks the rules in {cells} cells")]
+    BrokenModel { cells: usize },
+    #[error("I/O Error")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Sudoku(#[from] sudoku::Error),
+    #[error(transparent)]
+    Model(#[from] sudoku::ModelError),
+    #[error(transparent)]
+    Bench(#[from] bench::Error)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command<'a> {
+    Batch(Mode, &'a Path),
+    Bench(Option<&'a Path>),
+    Cnf(&'a Path),
+    Model(&'a Path, &'a Path)
+}
+
+pub fn run(args: &[String]) -> Result<(), Error> {
+    match parse_args(args)? {
+        Command::Batch(mode, path) => {
+            let puzzles = sudoku::read_puzzles(path)?;
+
+            for report in sudoku::run_batch(&puzzles, mode) {
+                println!("{}", format_report(&report));
+            }
+        }
+        Command::Bench(baseline) => bench::run(baseline)?,
+        Command::Cnf(path) => print!("{}", Sudoku::from_file(path)?.to_cnf().to_dimacs()),
+        Command::Model(path, model) => {
+            let solution = Sudoku::from_file(path)?.read_model(&std::fs::read_to_string(model)?)?;
+            println!("{}", solution.to_line());
+
+            let wrong = solution.check_rules();
+            if !wrong.is_empty() {
+                return Err(Error::BrokenModel { cells: wrong.len() });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_args(args: &[String]) -> Result<Command<'_>, Error> {
+    let words: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match words[..] {
+        ["solve", path] => Ok(Command::Batch(Mode::Solve, Path::new(path))),
+        ["rate", path] => Ok(Command::Batch(Mode::Rate, Path::new(path))),
+        ["bench"] => Ok(Command::Bench(None)),
+        ["bench", path] => Ok(Command::Bench(Some(Path::new(path)))),
+        ["cnf", path] => Ok(Command::Cnf(Path::new(path))),
+        ["model", path, model] => Ok(Command::Model(Path::new(path), Path::new(model))),
+        _ => Err(Error::Usage)
+    }
+}
+
+fn format_report(report: &Report) -> String {
+    let solution = report.solution.as_ref().map_or("-".to_string(), |solution| solution.to_line());
+    let rating = match report.rating {
+        None => "-".to_string(),
+        Some(Rating::Guessing) => "guessing".to_string(),
+        Some(Rating::Logic(technique)) => format!("{:?}", technique)
+    };
+
+    format!("{}\t{}\t{}\t{}", solution, report.count, rating, report.time.as_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn args_and_output() {
+        let args = |words: &[&str]| words.iter().map(|word| word.to_string()).collect::<Vec<_>>();
+
+        let solve = args(&["solve", "tests/batch"]);
+        assert_eq!(parse_args(&solve).ok(), Some(Command::Batch(Mode::Solve, Path::new("tests/batch"))));
+        assert!(matches!(parse_args(&args(&["rate", "a"])), Ok(Command::Batch(Mode::Rate, _))));
+        assert!(matches!(parse_args(&args(&["bench"])), Ok(Command::Bench(None))));
+        assert!(matches!(parse_args(&args(&["cnf", "a"])), Ok(Command::Cnf(_))));
+        assert!(matches!(parse_args(&args(&["model", "a", "b"])), Ok(Command::Model(_, _))));
+        assert!(matches!(parse_args(&args(&["model", "a"])), Err(Error::Usage)));
+        assert!(matches!(parse_args(&args(&["guess", "a"])), Err(Error::Usage)));
+        assert!(matches!(parse_args(&args(&["rate"])), Err(Error::Usage)));
+
+        let report = Report {
+            solution: None,
+            count: 0,
+            rating: None,
+            time: Duration::from_millis(12)
+        };
+        assert_eq!(format_report(&report), "-\t0\t-\t12");
+
+        let report = Report { solution: Some(Sudoku::new(4)), count: 2, rating: Some(Rating::Guessing), ..report };
+        assert_eq!(format_report(&report), "................\t2\tguessing\t12");
+    }
+}
@@ -0,0 +1,29 @@
+// Config files are made of `key = value` lines. Blank lines and lines starting
+// with '#' are ignored.
+
+// Yields (line number, key, value) for every entry, or Err(line number) for
+// lines that don't have an '='. Line numbers start at 1.
+pub fn entries(text: &str) -> impl Iterator<Item = Result<(usize, &str, &str), usize>> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| {
+            line.split_once('=')
+                .map(|(key, value)| (i, key.trim(), value.trim()))
+                .ok_or(i)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let text = "# comment\n\n a = b \nc=d = e\nbroken\n";
+        let entries: Vec<_> = entries(text).collect();
+
+        assert_eq!(entries, vec![Ok((3, "a", "b")), Ok((4, "c", "d = e")), Err(5)]);
+    }
+}
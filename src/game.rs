@@ -1,97 +1,472 @@
-//use thiserror::Error;
 use std::time::{Duration, Instant};
 use std::collections::hash_set::HashSet;
 use crate::interface;
+use crate::keymap::Keymap;
+use crate::palette::{Palette, Themes};
+use crate::settings::Settings;
 use crate::sudoku;
-use interface::{Action, Direction};
-use sudoku::{Sudoku, Number};
+use sudoku::{Candidates, Chess, Sudoku, Number, Technique};
 
-pub fn run() -> Result<(), anyhow::Error> {
-//    sdl2::hint::set("SDL_HINT_VIDEO_X11_NET_WM_BYPASS_COMPOSITOR", "0");
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Select { row: usize, col: usize },
+    MoveSelection { dir: Direction },
+    Insert { number: u8 },
+    Delete,
+    Redraw,
+    Solve,
+    Check,
+    Generate,
+    NextTheme,
+    ToggleNotes,
+    Undo,
+    Hint,
+    // cycles the selected cell through empty, 1, 2, ... size
+    CycleNumber { forward: bool },
+    // moves the part of the board that is shown, when it doesn't fit
+    Scroll { dir: Direction },
+    // turns anti-knight or anti-king on or off for the puzzle
+    ToggleChess { rule: Chess },
+    // shows the chain of the next logical step, if it needs one
+    Explain
+}
 
-    let mut game_context = interface::initialize_sdl()?;
-    let mut sudoku = Sudoku::from_file(std::path::Path::new("tests/example"))?;
-    let time_per_frame = Duration::new(1, 0) / 60;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction { Up, Down, Left, Right }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Highlight {
+    None,
+    // shares a row, column or box with the selected cell
+    Peer,
+    // has the same digit as the selected cell, or a note with it
+    SameDigit
+}
+
+// Pencil marks of every cell, indexed as [row][col]
+pub type Notes = Vec<Vec<HashSet<u8>>>;
+
+// What undo goes back to
+struct Snapshot {
+    sudoku: Sudoku,
+    notes: Notes
+}
+
+// Everything the game knows about, independent of how it's drawn or where the
+// actions come from
+pub struct GameState {
+    pub sudoku: Sudoku,
     // (row, col)
-    let mut selection: Option<(usize, usize)> = None;
-    let mut redraw = true;
-    let mut errors = HashSet::new();
+    pub selection: Option<(usize, usize)>,
+    pub errors: HashSet<(usize, usize)>,
+    pub notes: Notes,
+    // numbers are inserted as notes instead of answers
+    pub notes_mode: bool,
+    pub themes: Themes,
+    // top left cell shown and how many cells a side fit in the window, which
+    // is less than the size of the board for big ones like Samurai
+    pub scroll: (usize, usize),
+    pub view: usize,
+    // (row, col, number) candidates of the last explained chain, drawn as
+    // arrows until the board changes
+    pub chain: Vec<(usize, usize, u8)>,
+    pub redraw: bool,
+    pub quit: bool,
+    history: Vec<Snapshot>
+}
 
-    'game: loop {
-        let now = Instant::now();
+impl GameState {
+    pub fn new(sudoku: Sudoku) -> GameState {
+        let notes = empty_notes(sudoku.size());
+        let view = sudoku.size();
 
-        // process input
-        let (x, y) = game_context.canvas.window().drawable_size();
-        match interface::check_input(
-            &mut game_context.event_pump,
-            x.try_into()?,
-            y.try_into()?
-        ) {
-            Some(Action::Quit) => {
-                break 'game;
+        GameState {
+            sudoku,
+            selection: None,
+            errors: HashSet::new(),
+            notes,
+            notes_mode: false,
+            themes: Themes::new(None),
+            scroll: (0, 0),
+            view,
+            chain: Vec::new(),
+            redraw: true,
+            quit: false,
+            history: Vec::new()
+        }
+    }
+
+    pub fn apply(&mut self, action: Action) {
+        match action {
+            Action::Quit => {
+                self.quit = true;
             }
-            Some(Action::Redraw) => {
-                redraw = true;
+            Action::Redraw => {
+                self.redraw = true;
             }
-            Some(Action::Select { row, col } ) => {
-                selection = Some((row, col));
-                redraw = true;
+            Action::Select { row, col } => {
+                if self.sudoku.in_puzzle(row, col) {
+                    self.selection = Some((row, col));
+                    self.redraw = true;
+                }
             }
-            Some(Action::MoveSelection { dir } ) => {
-                selection = move_selection(selection, dir);
+            Action::MoveSelection { dir } => {
+                // jumps over the holes between grids, or stays if there's only
+                // holes that way
+                let mut next = self.selection;
+                loop {
+                    let moved = move_selection(next, dir, self.sudoku.size());
+                    if moved == next {
+                        break;
+                    }
 
-                redraw = true;
+                    next = moved;
+                    if let Some((row, col)) = next {
+                        if self.sudoku.in_puzzle(row, col) {
+                            self.selection = next;
+                            break;
+                        }
+                    }
+                }
+
+                self.follow_selection();
+                self.redraw = true;
+            }
+            Action::Scroll { dir } => {
+                let last = self.sudoku.size().saturating_sub(self.view);
+                let (row, col) = self.scroll;
+
+                self.scroll = match dir {
+                    Direction::Up => (row.saturating_sub(1), col),
+                    Direction::Down => ((row + 1).min(last), col),
+                    Direction::Left => (row, col.saturating_sub(1)),
+                    Direction::Right => (row, (col + 1).min(last))
+                };
+                self.redraw = true;
             }
-            Some(Action::Insert { number } ) => {
+            Action::Insert { number } => {
+                if number == 0 || number as usize > self.sudoku.digits() {
+                    return;
+                }
+
                 // if there is something selected
-                if let Some((row, col)) = selection {
-                    // if it's not a given number
-                    match sudoku.check_position(row, col) {
-                        Number::Empty | Number::Answer(_) => {
-                            sudoku.insert_number(row, col, number);
-                            redraw = true;
-                        }
-                        _ => {}
+                if let Some((row, col)) = self.selection {
+                    if self.notes_mode {
+                        self.toggle_note(row, col, number);
+                    } else {
+                        self.set_answer(row, col, Some(number));
                     }
                 }
             }
-            Some(Action::Delete) => {
-                if let Some((row, col)) = selection {
-                    sudoku.delete_number(row, col);
-                    redraw = true;
+            Action::Delete => {
+                if let Some((row, col)) = self.selection {
+                    self.edit(|state| {
+                        state.sudoku.delete_number(row, col);
+                        state.notes[row][col].clear();
+                    });
                 }
             }
-            Some(Action::Solve) => {
-                if let Some(sol) = sudoku.solve() {
-                    sudoku = sol;
-                    redraw = true;
+            Action::CycleNumber { forward } => {
+                if let Some((row, col)) = self.selection {
+                    let size = self.sudoku.digits() as u8;
+                    let next = match (self.sudoku.check_position(row, col), forward) {
+                        (Number::Given(_), _) => return,
+                        (Number::Empty, true) => Some(1),
+                        (Number::Empty, false) => Some(size),
+                        (Number::Answer(n), true) => (n < size).then_some(n + 1),
+                        (Number::Answer(n), false) => (n > 1).then_some(n - 1)
+                    };
+
+                    self.set_answer(row, col, next);
                 }
             }
-            Some(Action::Check) => {
-                errors = sudoku.check_rules();
-                redraw = true;
+            Action::Solve => {
+                let now = std::time::Instant::now();
+                let (solution, guessed) = self.sudoku.solve_guessing();
+                let elapsed = now.elapsed();
+
+                println!("solved in {}.{:03}s{}.",
+                    elapsed.as_secs(),
+                    elapsed.subsec_millis(),
+                    if guessed { ", requires guessing" } else { "" }
+                );
+
+                if let Some(sol) = solution {
+                    self.edit(|state| state.sudoku = sol);
+                }
+            }
+            Action::Hint => {
+                self.hint();
+            }
+            Action::Explain => {
+                self.explain();
+            }
+            Action::Undo => {
+                if let Some(snapshot) = self.history.pop() {
+                    self.sudoku = snapshot.sudoku;
+                    self.notes = snapshot.notes;
+                    self.redraw = true;
+                }
+            }
+            Action::ToggleNotes => {
+                self.notes_mode = !self.notes_mode;
+                self.redraw = true;
+            }
+            Action::Check => {
+                self.errors = self.sudoku.check_rules();
+                self.redraw = true;
+            }
+            Action::Generate => {
+                // the board stays as it is when its clues can't be made again
+                self.sudoku = match self.sudoku.generate_like() {
+                    Ok(sudoku) => sudoku,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                };
+                self.notes = empty_notes(self.sudoku.size());
+                self.history.clear();
+                self.selection = None;
+                self.errors.clear();
+                self.redraw = true;
+            }
+            Action::ToggleChess { rule } => {
+                self.edit(|state| {
+                    state.sudoku.toggle_constraint(rule);
+                });
+                self.errors = self.sudoku.check_rules();
+            }
+            Action::NextTheme => {
+                self.themes.next();
+                self.redraw = true;
+            }
+        }
+    }
+
+    pub fn highlight(&self, row: usize, col: usize) -> Highlight {
+        let Some((sel_row, sel_col)) = self.selection else {
+            return Highlight::None;
+        };
+
+        let selected = self.sudoku.check_position(sel_row, sel_col);
+        if selected.compare(self.sudoku.check_position(row, col)) {
+            return Highlight::SameDigit;
+        }
+
+        if let Number::Given(n) | Number::Answer(n) = selected {
+            if self.sudoku.check_position(row, col) == Number::Empty
+                && self.notes[row][col].contains(&n)
+            {
+                return Highlight::SameDigit;
+            }
+        }
+
+        if self.sudoku.are_peers((row, col), (sel_row, sel_col)) {
+            Highlight::Peer
+        } else {
+            Highlight::None
+        }
+    }
+
+    // Sets how many cells a side fit in the window, keeping the scroll inside
+    // the board
+    pub fn set_view(&mut self, view: usize) {
+        let view = view.clamp(1, self.sudoku.size().max(1));
+
+        if view != self.view {
+            self.view = view;
+            self.follow_selection();
+            self.redraw = true;
+        }
+    }
+
+    // Scrolls just enough for the selection to be shown
+    fn follow_selection(&mut self) {
+        let last = self.sudoku.size().saturating_sub(self.view);
+        let (mut top, mut left) = self.scroll;
+
+        if let Some((row, col)) = self.selection {
+            top = top.min(row).max((row + 1).saturating_sub(self.view));
+            left = left.min(col).max((col + 1).saturating_sub(self.view));
+        }
+
+        self.scroll = (top.min(last), left.min(last));
+    }
+
+    // Writes (or erases, with None) the answer of a cell that isn't a given
+    fn set_answer(&mut self, row: usize, col: usize, number: Option<u8>) {
+        if let Number::Given(_) = self.sudoku.check_position(row, col) {
+            return;
+        }
+
+        self.edit(|state| {
+            match number {
+                Some(n) => state.sudoku.insert_number(row, col, n),
+                None => state.sudoku.delete_number(row, col)
+            }
+            state.notes[row][col].clear();
+        });
+    }
+
+    fn toggle_note(&mut self, row: usize, col: usize, number: u8) {
+        if self.sudoku.check_position(row, col) != Number::Empty {
+            return;
+        }
+
+        self.edit(|state| {
+            let notes = &mut state.notes[row][col];
+            if !notes.remove(&number) {
+                notes.insert(number);
+            }
+        });
+    }
+
+    // Fills the selected cell with its value in the solution, or the first cell
+    // that is empty or wrong if the selected one is already right
+    fn hint(&mut self) {
+        let Some(solution) = self.sudoku.without_answers().solve() else {
+            return;
+        };
+
+        let is_wrong = |&(row, col): &(usize, usize)| {
+            !self.sudoku.check_position(row, col).compare(solution.check_position(row, col))
+        };
+        let target = self.selection
+            .filter(is_wrong)
+            .or_else(|| solution.iterate().map(|(row, col, _)| (row, col)).find(is_wrong));
+
+        if let Some((row, col)) = target {
+            if let Number::Given(n) | Number::Answer(n) = solution.check_position(row, col) {
+                self.selection = Some((row, col));
+                self.set_answer(row, col, Some(n));
+            }
+        }
+    }
+
+    // Goes through the steps a person could take on the board without
+    // guessing until one needs a chain, keeping it to be drawn. Nothing is
+    // filled in. Steps that need one solution are only taken for puzzles
+    // that have one.
+    fn explain(&mut self) {
+        let mut sudoku = self.sudoku.clone();
+        let mut candidates = Candidates::new(&sudoku);
+        candidates.set_unique(self.sudoku.without_answers().count_solutions(2) == 1);
+
+        self.chain = std::iter::from_fn(|| candidates.step(&mut sudoku, Technique::HARDEST))
+            .find(|deduction| !deduction.chain.is_empty())
+            .map(|deduction| deduction.chain)
+            .unwrap_or_default();
+        self.redraw = true;
+    }
+
+    // Runs a change on the board, remembering how it was before for undo
+    fn edit<F: FnOnce(&mut GameState)>(&mut self, change: F) {
+        let before = Snapshot { sudoku: self.sudoku.clone(), notes: self.notes.clone() };
+
+        change(self);
+
+        if before.sudoku != self.sudoku || before.notes != self.notes {
+            self.history.push(before);
+            self.chain.clear();
+            self.redraw = true;
+        }
+    }
+
+    // Applies actions in order, stopping at the first one that quits the game
+    pub fn apply_all<I: IntoIterator<Item = Action>>(&mut self, actions: I) {
+        for action in actions {
+            self.apply(action);
+
+            if self.quit {
+                break;
             }
-            Some(Action::Generate) => {
-                sudoku = Sudoku::generate(9);
-                redraw = true;
+        }
+    }
+}
+
+pub fn run() -> Result<(), anyhow::Error> {
+//    sdl2::hint::set("SDL_HINT_VIDEO_X11_NET_WM_BYPASS_COMPOSITOR", "0");
+
+    let mut game_context = interface::initialize_sdl()?;
+    let sudoku = Sudoku::from_file(std::path::Path::new("tests/example"))?;
+    let keymap_path = std::path::Path::new("keymap.cfg");
+    // a file that can't be read is reported and the defaults are used, so a
+    // mistake in one doesn't keep the game from starting
+    let keymap = if keymap_path.exists() {
+        Keymap::from_file(keymap_path).unwrap_or_else(|e| {
+            eprintln!("keymap.cfg: {}, using the default keys", e);
+            Keymap::default()
+        })
+    } else {
+        Keymap::default()
+    };
+    let palette_path = std::path::Path::new("palette.cfg");
+    let custom_palette = if palette_path.exists() {
+        match Palette::from_file(palette_path) {
+            Ok(palette) => Some(palette),
+            Err(e) => {
+                eprintln!("palette.cfg: {}, no custom theme", e);
+                None
             }
-            None => {}
+        }
+    } else {
+        None
+    };
+    let settings_path = std::path::Path::new("settings.cfg");
+    let mut settings = if settings_path.exists() {
+        let (settings, skipped) = Settings::from_file(settings_path).unwrap_or_else(|e| {
+            eprintln!("settings.cfg: {}, using the default settings", e);
+            (Settings::default(), Vec::new())
+        });
+
+        for e in skipped {
+            eprintln!("settings.cfg: {}, skipped", e);
         }
 
-        // run logic
+        settings
+    } else {
+        Settings::default()
+    };
+    let time_per_frame = Duration::new(1, 0) / 60;
+    let mut state = GameState::new(sudoku);
+    state.themes = Themes::new(custom_palette);
 
+    // the saved theme stays until the player picks another one, so a custom
+    // theme comes back once palette.cfg does
+    if !state.themes.select(&settings.theme) {
+        let missing = if settings.theme == "custom" { " (it needs palette.cfg)" } else { "" };
+        eprintln!("No theme {:?}{}, using {}", settings.theme, missing, state.themes.name());
+    }
+    let mut theme = state.themes.name().to_string();
+
+    while !state.quit {
+        let now = Instant::now();
+
+        // process input
+        let (x, y) = game_context.canvas.window().drawable_size();
+        let layout = interface::Layout::new(x, y, &state);
+        state.set_view(layout.visible());
+        let actions = interface::check_input(&mut game_context.event_pump, &keymap, &layout);
+        state.apply_all(actions);
+
+        if state.themes.name() != theme {
+            theme = state.themes.name().to_string();
+            settings.theme = theme.clone();
+            if let Err(e) = settings.save(settings_path) {
+                eprintln!("{}", e);
+            }
+        }
 
         // render
-        if redraw {
-            if let Err(e) = interface::render_window(
-                &mut game_context,
-                &sudoku,
-                &selection,
-                &errors
-            ) {
+        if state.redraw {
+            let (x, y) = game_context.canvas.window().drawable_size();
+            let layout = interface::Layout::new(x, y, &state);
+            if let Err(e) = interface::render_window(&mut game_context, &state, &layout) {
                 eprintln!("{}", e);
             }
-            redraw = false;
+            state.redraw = false;
         }
 
         ::std::thread::sleep(time_per_frame.saturating_sub(now.elapsed()));
@@ -100,17 +475,267 @@ pub fn run() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+fn empty_notes(size: usize) -> Notes {
+    vec![vec![HashSet::new(); size]; size]
+}
+
 fn move_selection(
     selection: Option<(usize, usize)>,
-    direction: Direction
+    direction: Direction,
+    size: usize
 ) -> Option<(usize, usize)> {
+    let last = size.saturating_sub(1);
+
     selection.map({ |(row, col)|
         match direction {
             Direction::Up => (row.saturating_sub(1), col),
-            Direction::Down => ((row + 1).min(8), col),
+            Direction::Down => ((row + 1).min(last), col),
             Direction::Left => (row, col.saturating_sub(1)),
-            Direction::Right => (row, (col + 1).min(8))
+            Direction::Right => (row, (col + 1).min(last))
         }
     })
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sudoku::Regenerate;
+
+    #[test]
+    fn move_selection_stays_inside_grid() {
+        assert_eq!(move_selection(Some((0, 0)), Direction::Up, 9), Some((0, 0)));
+        assert_eq!(move_selection(Some((0, 0)), Direction::Left, 9), Some((0, 0)));
+        assert_eq!(move_selection(Some((8, 8)), Direction::Down, 9), Some((8, 8)));
+        assert_eq!(move_selection(Some((8, 8)), Direction::Right, 9), Some((8, 8)));
+        assert_eq!(move_selection(Some((4, 4)), Direction::Down, 9), Some((5, 4)));
+        assert_eq!(move_selection(None, Direction::Down, 9), None);
+    }
+
+    #[test]
+    fn insert_needs_selection() {
+        let mut state = GameState::new(Sudoku::new(9));
+
+        state.apply(Action::Insert { number: 5 });
+        assert!(state.sudoku.iterate().all(|(_, _, n)| *n == Number::Empty));
+
+        state.apply_all([
+            Action::Select { row: 1, col: 2 },
+            Action::MoveSelection { dir: Direction::Right },
+            Action::Insert { number: 5 }
+        ]);
+        assert_eq!(state.sudoku.check_position(1, 3), Number::Answer(5));
+    }
+
+    #[test]
+    fn givens_cannot_be_changed() {
+        let mut sudoku = Sudoku::new(9);
+        sudoku.insert_given(0, 0, 3);
+        let mut state = GameState::new(sudoku);
+
+        state.apply_all([
+            Action::Select { row: 0, col: 0 },
+            Action::Insert { number: 4 },
+            Action::Delete
+        ]);
+        assert_eq!(state.sudoku.check_position(0, 0), Number::Given(3));
+    }
+
+    #[test]
+    fn check_reports_errors() {
+        let mut state = GameState::new(Sudoku::new(9));
+
+        state.apply_all([
+            Action::Select { row: 0, col: 0 },
+            Action::Insert { number: 7 },
+            Action::MoveSelection { dir: Direction::Right },
+            Action::Insert { number: 7 },
+            Action::Check
+        ]);
+        assert_eq!(state.errors, HashSet::from([(0, 0), (0, 1)]));
+    }
+
+    #[test]
+    fn toggle_chess_rules() {
+        let mut state = GameState::new(Sudoku::new(9));
+
+        state.apply_all([
+            Action::Select { row: 0, col: 2 },
+            Action::Insert { number: 7 },
+            Action::Select { row: 1, col: 4 },
+            Action::Insert { number: 7 },
+            Action::ToggleChess { rule: Chess::AntiKnight }
+        ]);
+        assert_eq!(state.errors, HashSet::from([(0, 2), (1, 4)]));
+
+        state.apply(Action::ToggleChess { rule: Chess::AntiKnight });
+        assert!(state.errors.is_empty());
+        assert!(state.sudoku.constraints().is_empty());
+
+        // like any other change of the board
+        state.apply(Action::Undo);
+        assert_eq!(state.sudoku.constraints().len(), 1);
+    }
+
+    #[test]
+    fn generate_keeps_the_rules() -> Result<(), anyhow::Error> {
+        let mut state = GameState::new(Sudoku::from_file(std::path::Path::new("tests/thermo"))?);
+        state.apply(Action::Generate);
+        assert!(state.sudoku.constraints().iter().all(|c| c.regenerate() == Regenerate::Thermometer));
+        assert!(state.sudoku.check_rules().is_empty());
+
+        // the cages of a killer can't be made again, so it stays
+        let killer = Sudoku::from_file(std::path::Path::new("tests/killer"))?;
+        let mut state = GameState::new(killer.clone());
+        state.apply_all([
+            Action::Select { row: 0, col: 0 },
+            Action::Generate
+        ]);
+        assert_eq!(state.sudoku, killer);
+        assert_eq!(state.selection, Some((0, 0)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn highlight_peers_and_digits() {
+        let mut sudoku = Sudoku::new(9);
+        sudoku.insert_given(0, 0, 4);
+        sudoku.insert_given(8, 8, 4);
+        sudoku.insert_given(5, 5, 2);
+        let mut state = GameState::new(sudoku);
+
+        assert_eq!(state.highlight(0, 1), Highlight::None);
+
+        state.apply(Action::Select { row: 0, col: 0 });
+        assert_eq!(state.highlight(0, 0), Highlight::SameDigit);
+        assert_eq!(state.highlight(8, 8), Highlight::SameDigit);
+        assert_eq!(state.highlight(0, 7), Highlight::Peer);
+        assert_eq!(state.highlight(6, 0), Highlight::Peer);
+        assert_eq!(state.highlight(2, 2), Highlight::Peer);
+        assert_eq!(state.highlight(5, 5), Highlight::None);
+        assert_eq!(state.highlight(3, 3), Highlight::None);
+    }
+
+    #[test]
+    fn notes_and_undo() {
+        let mut state = GameState::new(Sudoku::new(9));
+
+        state.apply_all([
+            Action::Select { row: 4, col: 4 },
+            Action::ToggleNotes,
+            Action::Insert { number: 2 },
+            Action::Insert { number: 6 },
+            Action::Insert { number: 2 }
+        ]);
+        assert_eq!(state.notes[4][4], HashSet::from([6]));
+        assert_eq!(state.sudoku.check_position(4, 4), Number::Empty);
+
+        state.apply(Action::Select { row: 0, col: 0 });
+        state.apply_all([Action::ToggleNotes, Action::Insert { number: 6 }]);
+        assert_eq!(state.highlight(4, 4), Highlight::SameDigit);
+
+        state.apply_all([Action::Select { row: 4, col: 4 }, Action::Insert { number: 3 }]);
+        assert_eq!(state.sudoku.check_position(4, 4), Number::Answer(3));
+        assert!(state.notes[4][4].is_empty());
+
+        state.apply(Action::Undo);
+        assert_eq!(state.sudoku.check_position(4, 4), Number::Empty);
+        assert_eq!(state.notes[4][4], HashSet::from([6]));
+
+        state.apply_all([Action::Undo, Action::Undo, Action::Undo, Action::Undo]);
+        assert_eq!(state.sudoku.check_position(0, 0), Number::Empty);
+        assert!(state.notes[4][4].is_empty());
+    }
+
+    #[test]
+    fn cycle_number() {
+        let mut state = GameState::new(Sudoku::new(9));
+
+        state.apply_all([
+            Action::Select { row: 0, col: 0 },
+            Action::CycleNumber { forward: true },
+            Action::CycleNumber { forward: true }
+        ]);
+        assert_eq!(state.sudoku.check_position(0, 0), Number::Answer(2));
+
+        state.apply_all([
+            Action::CycleNumber { forward: false },
+            Action::CycleNumber { forward: false }
+        ]);
+        assert_eq!(state.sudoku.check_position(0, 0), Number::Empty);
+
+        state.apply(Action::CycleNumber { forward: false });
+        assert_eq!(state.sudoku.check_position(0, 0), Number::Answer(9));
+    }
+
+    #[test]
+    fn hint_fills_a_cell_from_the_solution() -> Result<(), anyhow::Error> {
+        let sudoku = Sudoku::from_file(std::path::Path::new("tests/example"))?;
+        let solution = sudoku.solve().ok_or(anyhow::anyhow!("no solution"))?;
+        let mut state = GameState::new(sudoku);
+
+        state.apply_all([Action::Select { row: 0, col: 0 }, Action::Hint]);
+        assert_eq!(state.selection, Some((0, 0)));
+        assert!(state.sudoku.check_position(0, 0).compare(solution.check_position(0, 0)));
+
+        // the selected cell is already right, so it goes to the next one
+        state.apply(Action::Hint);
+        assert_eq!(state.selection, Some((0, 1)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn explain_keeps_the_chain_until_an_edit() -> Result<(), anyhow::Error> {
+        let sudoku = Sudoku::from_file(std::path::Path::new("tests/xywing"))?;
+        let mut state = GameState::new(sudoku.clone());
+
+        // a few eliminations without chains come first
+        state.apply(Action::Explain);
+        assert_eq!(state.chain, vec![(8, 1, 4), (8, 1, 3), (0, 1, 3), (0, 1, 8), (1, 2, 8), (1, 2, 4)]);
+        assert_eq!(state.sudoku, sudoku);
+
+        state.apply_all([Action::Select { row: 0, col: 1 }, Action::Insert { number: 3 }]);
+        assert!(state.chain.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn quit_stops_processing() {
+        let mut state = GameState::new(Sudoku::new(9));
+
+        state.apply_all([
+            Action::Select { row: 0, col: 0 },
+            Action::Quit,
+            Action::Insert { number: 1 }
+        ]);
+        assert!(state.quit);
+        assert_eq!(state.sudoku.check_position(0, 0), Number::Empty);
+    }
+
+    #[test]
+    fn selection_skips_holes_and_scrolls() {
+        let mut sudoku = Sudoku::new(21);
+        sudoku.set_grids(9, vec![(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)]).unwrap();
+        let mut state = GameState::new(sudoku);
+        state.set_view(10);
+
+        // between the top grids there's a hole, which can't be selected and
+        // is jumped over
+        state.apply_all([
+            Action::Select { row: 0, col: 8 },
+            Action::Select { row: 0, col: 9 },
+            Action::MoveSelection { dir: Direction::Right }
+        ]);
+        assert_eq!(state.selection, Some((0, 12)));
+        assert_eq!(state.scroll, (0, 3));
+
+        // nothing above the top row
+        state.apply(Action::MoveSelection { dir: Direction::Up });
+        assert_eq!(state.selection, Some((0, 12)));
+
+        state.apply_all([Action::Scroll { dir: Direction::Right }; 20]);
+        assert_eq!(state.scroll, (0, 11));
+    }
+}
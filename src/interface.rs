@@ -1,11 +1,16 @@
-use std::collections::{HashMap, hash_set::HashSet};
-use crate::sudoku::{Number, Sudoku};
+use std::collections::hash_set::HashSet;
+use crate::game::{Action, GameState, Highlight};
+use crate::keymap::{Keymap, Modifiers};
+use crate::palette::Palette;
+use crate::sudoku::{Number, Shape, Sudoku};
 use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
 use anyhow::anyhow;
 use sdl2::event::{Event, WindowEvent};
-use sdl2::keyboard::Keycode;
-use sdl2::mouse::MouseButton;
+use sdl2::mouse::{MouseButton, MouseWheelDirection};
+
+const FONT_PATH: &str = "/usr/share/fonts/truetype/OpenSans-ExtraBold.ttf";
 
 pub struct SdlContext {
     _sdl_context: sdl2::Sdl,
@@ -15,26 +20,11 @@ pub struct SdlContext {
     ttf_context: sdl2::ttf::Sdl2TtfContext
 }
 
-pub enum Action {
-    Quit,
-    Select { row: usize, col: usize },
-    MoveSelection { dir: Direction },
-    Insert { number: u8 },
-    Delete,
-    Redraw,
-    Solve,
-    Check,
-    Generate
-}
-
-#[derive(Clone, Copy)]
-pub enum Direction { Up, Down, Left, Right }
-
 pub fn initialize_sdl() -> Result<SdlContext, anyhow::Error> {
     let sdl_context = sdl2::init().map_err(|e| anyhow!(e))?;
     let video_subsystem = sdl_context.video().map_err(|e| anyhow!(e))?;
 
-    let window = video_subsystem.window("Sudoku", 800, 800)
+    let window = video_subsystem.window("Sudoku", 1060, 800)
         .position_centered()
         //.resizable()
         .build()
@@ -54,79 +44,237 @@ pub fn initialize_sdl() -> Result<SdlContext, anyhow::Error> {
     })
 }
 
-pub fn check_input(event_pump: &mut sdl2::EventPump, width: usize, length: usize) -> Option<Action> {
-    for event in event_pump.poll_iter() {
-        match event {
-            Event::Quit { .. }
-            | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
-                return Some(Action::Quit)
-            }
-            Event::MouseButtonDown {mouse_btn: MouseButton::Left, x, y, .. } => {
-                return Some(Action::Select { 
-                    row: y as usize * 9 / width,
-                    col: x as usize * 9 / length
-                })
-            }
-            Event::KeyDown { keycode: Some(Keycode::Delete), .. } => {
-                return Some(Action::Delete)
-            }
-            Event::KeyDown { keycode: Some(Keycode::S), .. } => {
-                return Some(Action::Solve)
-            }
-            Event::KeyDown { keycode: Some(Keycode::C), .. } => {
-                return Some(Action::Check)
-            }
-            Event::KeyDown { keycode: Some(Keycode::G), .. } => {
-                return Some(Action::Generate)
-            }
-            Event::KeyDown { keycode: Some(key), .. } => {
-                return check_input_numbers(&key)
-                    .or(check_input_cursor(&key))
-            }
-            Event::Window { win_event: event, .. } => {
-                return check_input_window_event(&event)
-            }
-            _ => {}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Button {
+    Number(u8),
+    Notes,
+    Erase,
+    Undo,
+    Hint,
+    Check
+}
+
+impl Button {
+    fn action(self) -> Action {
+        match self {
+            Button::Number(number) => Action::Insert { number },
+            Button::Notes => Action::ToggleNotes,
+            Button::Erase => Action::Delete,
+            Button::Undo => Action::Undo,
+            Button::Hint => Action::Hint,
+            Button::Check => Action::Check
         }
     }
 
-    None
+    fn label(self) -> String {
+        match self {
+            Button::Number(number) => digit_char(number.into()).to_string(),
+            Button::Notes => "Notes".to_string(),
+            Button::Erase => "Erase".to_string(),
+            Button::Undo => "Undo".to_string(),
+            Button::Hint => "Hint".to_string(),
+            Button::Check => "Check".to_string()
+        }
+    }
 }
 
-fn check_input_numbers(key_pressed: &Keycode) -> Option<Action> {
-    let keys: HashMap<Keycode, u8> = HashMap::from([
-        (Keycode::Num1, 1),
-        (Keycode::Kp1, 1),
-        (Keycode::Num2, 2),
-        (Keycode::Kp2, 2),
-        (Keycode::Num3, 3),
-        (Keycode::Kp3, 3),
-        (Keycode::Num4, 4),
-        (Keycode::Kp4, 4),
-        (Keycode::Num5, 5),
-        (Keycode::Kp5, 5),
-        (Keycode::Num6, 6),
-        (Keycode::Kp6, 6),
-        (Keycode::Num7, 7),
-        (Keycode::Kp7, 7),
-        (Keycode::Num8, 8),
-        (Keycode::Kp8, 8),
-        (Keycode::Num9, 9),
-        (Keycode::Kp9, 9)
-    ]);
-
-    keys.get(key_pressed).map(|n| Action::Insert { number: *n })
+// Cells are never drawn smaller than this, big boards scroll instead
+const MIN_CELL: u32 = 40;
+
+// Where things go in the window: the board is a square on the left, and the
+// number pad with the toolbar under it goes on the right
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    size: usize,
+    digits: usize,
+    // cells a side that fit in the board, and the top left one of them
+    visible: usize,
+    scroll: (usize, usize),
+    // cells of room on each side of the grid for the clues outside of it
+    outside: usize,
+    board: Rect,
+    panel: Rect
 }
 
-fn check_input_cursor(key_pressed: &Keycode) -> Option<Action> {
-    let keys: HashMap<_, _> = HashMap::from([
-        (Keycode::Up, Direction::Up),
-        (Keycode::Down, Direction::Down),
-        (Keycode::Right, Direction::Right),
-        (Keycode::Left, Direction::Left)
-    ]);
+impl Layout {
+    pub fn new(width: u32, height: u32, state: &GameState) -> Layout {
+        let side = height.min(width * 3 / 4);
+        let margin = side / 40;
+        let size = state.sudoku.size();
+        let outside = state.sudoku.constraints().iter().any(|c| c.outside()) as usize;
+        let visible = size.min(((side / MIN_CELL) as usize).saturating_sub(2 * outside)).max(1);
+        let last = size.saturating_sub(visible);
+
+        Layout {
+            size,
+            digits: state.sudoku.digits(),
+            visible,
+            scroll: (state.scroll.0.min(last), state.scroll.1.min(last)),
+            outside,
+            board: Rect::new(0, 0, side, side),
+            panel: Rect::new(
+                (side + margin) as i32,
+                margin as i32,
+                width.saturating_sub(side + 2 * margin),
+                side.saturating_sub(2 * margin)
+            )
+        }
+    }
+
+    pub fn visible(&self) -> usize {
+        self.visible
+    }
+
+    // Cells out of the shown part of the board are outside of it too, which
+    // drawing clipped to the board hides
+    pub fn cell(&self, row: usize, col: usize) -> Rect {
+        let side = self.board.width() as i32;
+        let slots = self.slots() as i32;
+        let row = row as i32 - self.scroll.0 as i32 + self.outside as i32;
+        let col = col as i32 - self.scroll.1 as i32 + self.outside as i32;
+        let x_1 = col * side / slots;
+        let x_2 = (col + 1) * side / slots;
+        let y_1 = row * side / slots;
+        let y_2 = (row + 1) * side / slots;
+
+        Rect::new(
+            self.board.x() + x_1,
+            self.board.y() + y_1,
+            (x_2 - x_1) as u32,
+            (y_2 - y_1) as u32
+        )
+    }
+
+    pub fn cell_at(&self, x: i32, y: i32) -> Option<(usize, usize)> {
+        if !self.board.contains_point(Point::new(x, y)) {
+            return None;
+        }
+
+        let side = self.board.width() as usize;
+        let slot = |n: i32| (n as usize * self.slots() / side).checked_sub(self.outside)
+            .filter(|&i| i < self.visible);
+        let row = slot(y - self.board.y())? + self.scroll.0;
+        let col = slot(x - self.board.x())? + self.scroll.1;
+
+        (row < self.size && col < self.size).then_some((row, col))
+    }
+
+    // Cells a side that the board is divided in, counting the room for the
+    // clues outside the grid
+    fn slots(&self) -> usize {
+        self.visible + 2 * self.outside
+    }
+
+    pub fn cell_side(&self) -> f32 {
+        self.board.width() as f32 / self.slots() as f32
+    }
+
+    // Where a (row, col) position measured in cells is in the window
+    pub fn point(&self, (row, col): (f32, f32)) -> (f32, f32) {
+        let offset = |n: f32, scroll: usize| (n - scroll as f32 + self.outside as f32) * self.cell_side();
+
+        (
+            self.board.left() as f32 + offset(col, self.scroll.1),
+            self.board.top() as f32 + offset(row, self.scroll.0)
+        )
+    }
 
-    keys.get(key_pressed).map(|dir| Action::MoveSelection { dir: *dir })
+    // Columns of the number pad, about the square root of the digits
+    fn pad_columns(&self) -> usize {
+        (1..).find(|c| c * c >= self.digits).unwrap_or(1)
+    }
+
+    pub fn buttons(&self) -> Vec<(Button, Rect)> {
+        let cols = self.pad_columns();
+        let rows = self.digits.div_ceil(cols);
+        let tools = [Button::Notes, Button::Erase, Button::Undo, Button::Hint, Button::Check];
+
+        // the tools are half as tall as the numbers
+        let side = (self.panel.width() as usize / cols)
+            .min(self.panel.height() as usize * 2 / (2 * rows + tools.len() + 1));
+        let gap = side / 10;
+        let mut buttons = Vec::new();
+
+        for number in 1..=self.digits {
+            let row = (number - 1) / cols;
+            let col = (number - 1) % cols;
+
+            buttons.push((
+                Button::Number(number as u8),
+                Rect::new(
+                    self.panel.x() + (col * side) as i32,
+                    self.panel.y() + (row * side) as i32,
+                    (side - gap) as u32,
+                    (side - gap) as u32
+                )
+            ));
+        }
+
+        let top = self.panel.y() + (rows * side + side / 2) as i32;
+        for (i, tool) in tools.into_iter().enumerate() {
+            buttons.push((
+                tool,
+                Rect::new(
+                    self.panel.x(),
+                    top + (i * side / 2) as i32,
+                    (cols * side - gap) as u32,
+                    (side / 2 - gap) as u32
+                )
+            ));
+        }
+
+        buttons
+    }
+
+    pub fn button_at(&self, x: i32, y: i32) -> Option<Button> {
+        self.buttons().into_iter()
+            .find(|(_, rect)| rect.contains_point(Point::new(x, y)))
+            .map(|(button, _)| button)
+    }
+}
+
+// Drains every pending event, returning the actions they translate to in order
+pub fn check_input(
+    event_pump: &mut sdl2::EventPump,
+    keymap: &Keymap,
+    layout: &Layout
+) -> Vec<Action> {
+    event_pump.poll_iter()
+        .filter_map(|event| translate_event(&event, keymap, layout))
+        .collect()
+}
+
+pub fn translate_event(event: &Event, keymap: &Keymap, layout: &Layout) -> Option<Action> {
+    match event {
+        Event::Quit { .. } => {
+            Some(Action::Quit)
+        }
+        Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
+            if let Some((row, col)) = layout.cell_at(*x, *y) {
+                return Some(Action::Select { row, col });
+            }
+
+            layout.button_at(*x, *y).map(Button::action)
+        }
+        Event::MouseWheel { y, direction, .. } => {
+            let y = match direction {
+                MouseWheelDirection::Flipped => -y,
+                _ => *y
+            };
+
+            match y {
+                0 => None,
+                _ => Some(Action::CycleNumber { forward: y > 0 })
+            }
+        }
+        Event::KeyDown { keycode: Some(key), keymod, .. } => {
+            keymap.lookup(*key, Modifiers::from_sdl(*keymod))
+        }
+        Event::Window { win_event: event, .. } => {
+            check_input_window_event(event)
+        }
+        _ => None
+    }
 }
 
 fn check_input_window_event(event: &WindowEvent) -> Option<Action> {
@@ -144,31 +292,38 @@ fn check_input_window_event(event: &WindowEvent) -> Option<Action> {
 
 pub fn render_window(
     sdl: &mut SdlContext,
-    sudoku: &Sudoku,
-    selection: &Option<(usize, usize)>,
-    errors: &HashSet<(usize, usize)>
+    state: &GameState,
+    layout: &Layout
 ) -> Result<(), anyhow::Error> {
-    sdl.canvas.set_draw_color(Color::RGB(0, 0, 0));
+    let palette = state.themes.palette();
+    sdl.canvas.set_draw_color(palette.background);
     sdl.canvas.clear();
 
-    let width = sdl.canvas.window().drawable_size().0.try_into()?;
-    let height = sdl.canvas.window().drawable_size().1.try_into()?;
-    let white = Color::WHITE;
-    let grey = Color::GREY;
-    let red = Color::RGB(100, 0, 0);
+    // only the part of a big board that fits is drawn
+    sdl.canvas.set_clip_rect(layout.board);
+
+    // row, column, box and digit of the selection, or the shaded cells of
+    // the constraints
+    render_highlights(sdl, state, layout, palette)?;
 
     // squares with invalid numbers
-    render_error_squares(sdl, errors, width, height, &red)?;
+    render_error_squares(sdl, &state.errors, layout, &palette.error)?;
 
-    // render grid
-    render_grid(sdl, width, height, &white)?;
+    // render grid and the marks of the constraints
+    render_grid(sdl, &state.sudoku, layout, &palette.grid)?;
+    render_shapes(sdl, &state.sudoku, layout, palette)?;
 
     // render numbers
-    render_numbers(sdl, sudoku, width, height, &white, &grey)?;
+    render_numbers(sdl, &state.sudoku, layout, &palette.answer, &palette.given)?;
+    render_notes(sdl, state, layout, &palette.answer)?;
+    render_chain(sdl, &state.chain, layout, &palette.selection)?;
 
     // selection rectangle
-    let color = Color::RGB(30, 30, 220);
-    render_selection_rectangle(sdl, selection, width, height, &color)?;
+    render_selection_rectangle(sdl, &state.selection, layout, &palette.selection)?;
+    sdl.canvas.set_clip_rect(None);
+
+    // number pad and toolbar
+    render_panel(sdl, state, layout, palette)?;
 
     sdl.canvas.present();
 
@@ -177,26 +332,177 @@ pub fn render_window(
 
 fn render_grid(
     sdl: &mut SdlContext,
-    width: i16,
-    height: i16,
+    sudoku: &Sudoku,
+    layout: &Layout,
     color: &Color
 ) -> Result<(), anyhow::Error> {
-    for i in 0..=9 {
-        sdl.canvas.thick_line(
-            i * width / 9,
-            0,
-            i * width / 9,
-            height,
-            if i % 3 == 0 { 6 } else { 2 },
-            *color
-        ).map_err(|e| anyhow!(e))?;
+    let size = layout.size;
+
+    // thin lines around every cell, leaving the holes between grids empty
+    for (row, col, _) in sudoku.iterate() {
+        let rect = layout.cell(row, col);
+        let (x_1, x_2) = (rect.left() as i16, rect.right() as i16);
+        let (y_1, y_2) = (rect.top() as i16, rect.bottom() as i16);
+
+        sdl.canvas.thick_line(x_1, y_1, x_2, y_1, 2, *color).map_err(|e| anyhow!(e))?;
+        sdl.canvas.thick_line(x_1, y_2, x_2, y_2, 2, *color).map_err(|e| anyhow!(e))?;
+        sdl.canvas.thick_line(x_1, y_1, x_1, y_2, 2, *color).map_err(|e| anyhow!(e))?;
+        sdl.canvas.thick_line(x_2, y_1, x_2, y_2, 2, *color).map_err(|e| anyhow!(e))?;
+    }
+
+    // thick lines around the grids and between regions, a bit longer so they
+    // join at the corners
+    const THICK: i16 = 6;
+    let region = |row: usize, col: usize| sudoku.box_number(row, col);
 
-        sdl.canvas.thick_line(
-            0,
-            i * height / 9,
-            width,
-            i * height / 9,
-            if i % 3 == 0 { 6 } else { 2 },
+    for (row, col, _) in sudoku.iterate() {
+        let rect = layout.cell(row, col);
+        let (x_1, x_2) = (rect.left() as i16, rect.right() as i16);
+        let (y_1, y_2) = (rect.top() as i16, rect.bottom() as i16);
+        let half = THICK / 2;
+
+        if row == 0 || !sudoku.in_puzzle(row - 1, col) {
+            sdl.canvas.thick_line(x_1 - half, y_1, x_2 + half, y_1, THICK as u8, *color)
+                .map_err(|e| anyhow!(e))?;
+        }
+        if col == 0 || !sudoku.in_puzzle(row, col - 1) {
+            sdl.canvas.thick_line(x_1, y_1 - half, x_1, y_2 + half, THICK as u8, *color)
+                .map_err(|e| anyhow!(e))?;
+        }
+        if row + 1 == size || region(row, col) != region(row + 1, col) {
+            sdl.canvas.thick_line(x_1 - half, y_2, x_2 + half, y_2, THICK as u8, *color)
+                .map_err(|e| anyhow!(e))?;
+        }
+        if col + 1 == size || region(row, col) != region(row, col + 1) {
+            sdl.canvas.thick_line(x_2, y_1 - half, x_2, y_2 + half, THICK as u8, *color)
+                .map_err(|e| anyhow!(e))?;
+        }
+    }
+
+    Ok(())
+}
+
+// Draws what the constraints need on top of the grid, except the shaded cells
+// that go under the highlights
+fn render_shapes(
+    sdl: &mut SdlContext,
+    sudoku: &Sudoku,
+    layout: &Layout,
+    palette: &Palette
+) -> Result<(), anyhow::Error> {
+    let cell_side = layout.cell_side();
+    let point = |position| layout.point(position);
+
+    for shape in sudoku.constraints().iter().flat_map(|c| c.shapes(sudoku.size())) {
+        match shape {
+            Shape::Shade { .. } => {}
+            Shape::Line { from, to, width, ink } => {
+                let (x_1, y_1) = point(from);
+                let (x_2, y_2) = point(to);
+                let width = (width * cell_side).max(1.0) as u8;
+
+                sdl.canvas.thick_line(x_1 as i16, y_1 as i16, x_2 as i16, y_2 as i16, width, palette.ink(ink))
+                    .map_err(|e| anyhow!(e))?;
+            }
+            Shape::Circle { center, radius, filled, ink } => {
+                let (x, y) = point(center);
+                let radius = (radius * cell_side) as i16;
+
+                if filled {
+                    sdl.canvas.filled_circle(x as i16, y as i16, radius, palette.ink(ink))
+                        .map_err(|e| anyhow!(e))?;
+                } else {
+                    // two pixels wide
+                    for r in [radius - 1, radius] {
+                        sdl.canvas.circle(x as i16, y as i16, r, palette.ink(ink))
+                            .map_err(|e| anyhow!(e))?;
+                    }
+                }
+            }
+            Shape::Text { center, text, size, ink } => {
+                let (x, y) = point(center);
+                let font = sdl.ttf_context.load_font(FONT_PATH, ((size * cell_side) as u16).max(1))
+                    .map_err(|e| anyhow!(e))?;
+
+                print_text(&mut sdl.canvas, &font, &text, &palette.ink(ink), (x as i32, y as i32))?;
+            }
+            Shape::Outline { cells, ink } => {
+                render_outline(&mut sdl.canvas, &cells, layout, &palette.ink(ink))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Dashed lines a bit inside the border of a group of cells, like killer cages
+fn render_outline(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    cells: &[(usize, usize)],
+    layout: &Layout,
+    color: &Color
+) -> Result<(), anyhow::Error> {
+    let inside = |row: Option<usize>, col: Option<usize>| match (row, col) {
+        (Some(row), Some(col)) => cells.contains(&(row, col)),
+        _ => false
+    };
+
+    for &(row, col) in cells {
+        let rect = layout.cell(row, col);
+        let inset = rect.width() as i16 / 10;
+        let (left, right) = (rect.left() as i16, rect.right() as i16);
+        let (top, bottom) = (rect.top() as i16, rect.bottom() as i16);
+
+        let up = inside(row.checked_sub(1), Some(col));
+        let down = inside(Some(row + 1), Some(col));
+        let west = inside(Some(row), col.checked_sub(1));
+        let east = inside(Some(row), Some(col + 1));
+
+        // sides connect with the neighbours in the group
+        let x_1 = if west { left } else { left + inset };
+        let x_2 = if east { right } else { right - inset };
+        let y_1 = if up { top } else { top + inset };
+        let y_2 = if down { bottom } else { bottom - inset };
+
+        if !up {
+            dashed_line(canvas, (x_1, top + inset), (x_2, top + inset), color)?;
+        }
+        if !down {
+            dashed_line(canvas, (x_1, bottom - inset), (x_2, bottom - inset), color)?;
+        }
+        if !west {
+            dashed_line(canvas, (left + inset, y_1), (left + inset, y_2), color)?;
+        }
+        if !east {
+            dashed_line(canvas, (right - inset, y_1), (right - inset, y_2), color)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Only for horizontal and vertical lines
+fn dashed_line(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    from: (i16, i16),
+    to: (i16, i16),
+    color: &Color
+) -> Result<(), anyhow::Error> {
+    const DASH: i16 = 6;
+    const GAP: i16 = 4;
+
+    let length = (to.0 - from.0).abs().max((to.1 - from.1).abs());
+    let step_x = (to.0 - from.0).signum();
+    let step_y = (to.1 - from.1).signum();
+
+    for start in (0..length).step_by((DASH + GAP) as usize) {
+        let end = (start + DASH).min(length);
+
+        canvas.line(
+            from.0 + step_x * start,
+            from.1 + step_y * start,
+            from.0 + step_x * end,
+            from.1 + step_y * end,
             *color
         ).map_err(|e| anyhow!(e))?;
     }
@@ -204,20 +510,61 @@ fn render_grid(
     Ok(())
 }
 
+fn fill_cell(
+    sdl: &mut SdlContext,
+    layout: &Layout,
+    row: usize,
+    col: usize,
+    color: Color
+) -> Result<(), anyhow::Error> {
+    let rect = layout.cell(row, col);
+
+    sdl.canvas.box_(
+        rect.left() as i16,
+        rect.top() as i16,
+        rect.right() as i16,
+        rect.bottom() as i16,
+        color
+    ).map_err(|e| anyhow!(e))
+}
+
+fn render_highlights(
+    sdl: &mut SdlContext,
+    state: &GameState,
+    layout: &Layout,
+    palette: &Palette
+) -> Result<(), anyhow::Error> {
+    let mut shaded = std::collections::HashMap::new();
+    for shape in state.sudoku.constraints().iter().flat_map(|c| c.shapes(state.sudoku.size())) {
+        if let Shape::Shade { cell, ink } = shape {
+            shaded.insert(cell, palette.ink(ink));
+        }
+    }
+
+    for (row, col, _) in state.sudoku.iterate() {
+        let color = match state.highlight(row, col) {
+            Highlight::None => match shaded.get(&(row, col)) {
+                Some(color) => *color,
+                None => continue
+            },
+            Highlight::Peer => palette.peer,
+            Highlight::SameDigit => palette.same_digit
+        };
+
+        fill_cell(sdl, layout, row, col, color)?;
+    }
+
+    Ok(())
+}
+
 fn render_error_squares(
     sdl: &mut SdlContext,
     errors: &HashSet<(usize, usize)>,
-    width: i16,
-    height: i16,
+    layout: &Layout,
     color: &Color
 ) -> Result<(), anyhow::Error> {
     for (row, col) in errors {
-        let x_1 = (*col as i16) * width / 9;
-        let x_2 = (*col as i16 + 1) * width / 9;
-        let y_1 = (*row as i16) * height / 9;
-        let y_2 = (*row as i16 + 1) * height / 9;
-
-        sdl.canvas.box_(x_1, y_1, x_2, y_2, *color).map_err(|e| anyhow!(e))?;
+        fill_cell(sdl, layout, *row, *col, *color)?;
     }
 
     Ok(())
@@ -226,16 +573,16 @@ fn render_error_squares(
 fn render_selection_rectangle(
     sdl: &mut SdlContext,
     selection: &Option<(usize, usize)>,
-    width: i16,
-    height: i16,
+    layout: &Layout,
     color: &Color
 ) -> Result<(), anyhow::Error> {
     if let Some((row, col)) = selection {
-        let x_1 = (*col as i16) * width / 9;
-        let x_2 = (*col as i16 + 1) * width / 9;
-        let y_1 = (*row as i16) * height / 9;
-        let y_2 = (*row as i16 + 1) * height / 9;
-        
+        let rect = layout.cell(*row, *col);
+        let x_1 = rect.left() as i16;
+        let x_2 = rect.right() as i16;
+        let y_1 = rect.top() as i16;
+        let y_2 = rect.bottom() as i16;
+
         sdl.canvas.thick_line(x_1, y_1, x_2, y_1, 6, *color).map_err(|e| anyhow!(e))?;
         sdl.canvas.thick_line(x_1, y_2, x_2, y_2, 6, *color).map_err(|e| anyhow!(e))?;
         sdl.canvas.thick_line(x_1, y_1, x_1, y_2, 6, *color).map_err(|e| anyhow!(e))?;
@@ -248,27 +595,25 @@ fn render_selection_rectangle(
 fn render_numbers(
     sdl: &mut SdlContext,
     sudoku: &Sudoku,
-    width: i16,
-    height: i16,
+    layout: &Layout,
     color_input: &Color,
     color_given: &Color
 ) -> Result<(), anyhow::Error> {
     let font = sdl.ttf_context.load_font(
-        "/usr/share/fonts/truetype/OpenSans-ExtraBold.ttf",
-        (height / 9 - 10).try_into()?
+        FONT_PATH,
+        (layout.cell(0, 0).height() as u16).saturating_sub(10).max(1)
     ).map_err(|e| anyhow!(e))?;
 
     for (row, col, number) in sudoku.iterate() {
-        let pos_x = col as i32 * width as i32 / 9 + width as i32 / 18;
-        let pos_y = row as i32 * height as i32 / 9 + height as i32 / 18;
+        let center = layout.cell(row, col).center();
         match number {
             Number::Answer(val) => {
                 print_number(
                     &mut sdl.canvas,
                     &font,
-                    (*val).try_into()?,
+                    (*val).into(),
                     color_input,
-                    (pos_x, pos_y)
+                    (center.x(), center.y())
                 )?;
 
             }
@@ -276,9 +621,9 @@ fn render_numbers(
                 print_number(
                     &mut sdl.canvas,
                     &font,
-                    (*val).try_into()?,
+                    (*val).into(),
                     color_given,
-                    (pos_x, pos_y)
+                    (center.x(), center.y())
                 )?;
             }
             Number::Empty => {
@@ -289,6 +634,135 @@ fn render_numbers(
     Ok(())
 }
 
+// Notes go in a small grid inside the cell, each number in its own spot
+fn render_notes(
+    sdl: &mut SdlContext,
+    state: &GameState,
+    layout: &Layout,
+    color: &Color
+) -> Result<(), anyhow::Error> {
+    let cols = layout.pad_columns() as i32;
+    let font = sdl.ttf_context.load_font(
+        FONT_PATH,
+        (layout.cell(0, 0).height() as u16 / cols as u16).saturating_sub(4).max(1)
+    ).map_err(|e| anyhow!(e))?;
+
+    for (row, col, number) in state.sudoku.iterate() {
+        if *number != Number::Empty {
+            continue;
+        }
+
+        let rect = layout.cell(row, col);
+        for note in &state.notes[row][col] {
+            let i = *note as i32 - 1;
+            let x = rect.x() + (2 * (i % cols) + 1) * rect.width() as i32 / (2 * cols);
+            let y = rect.y() + (2 * (i / cols) + 1) * rect.height() as i32 / (2 * cols);
+
+            print_number(&mut sdl.canvas, &font, (*note).into(), color, (x, y))?;
+        }
+    }
+
+    Ok(())
+}
+
+// Arrows between the candidates of a chain, from where they are drawn as
+// notes. Its links are strong and weak in turns, so every other one is thick.
+fn render_chain(
+    sdl: &mut SdlContext,
+    chain: &[(usize, usize, u8)],
+    layout: &Layout,
+    color: &Color
+) -> Result<(), anyhow::Error> {
+    let cols = layout.pad_columns();
+    let position = |(row, col, n): (usize, usize, u8)| {
+        let i = n as usize - 1;
+        let offset = |slot: usize| (2 * slot + 1) as f32 / (2 * cols) as f32;
+
+        layout.point((row as f32 + offset(i / cols), col as f32 + offset(i % cols)))
+    };
+    let head = layout.cell_side() / (2 * cols) as f32;
+
+    for (i, link) in chain.windows(2).enumerate() {
+        let (from, to) = (position(link[0]), position(link[1]));
+        let width = if i % 2 == 0 { 3 } else { 1 };
+
+        sdl.canvas.thick_line(from.0 as i16, from.1 as i16, to.0 as i16, to.1 as i16, width, *color)
+            .map_err(|e| anyhow!(e))?;
+
+        // the point of the arrow is on the candidate, the back a note away
+        let length = (to.0 - from.0).hypot(to.1 - from.1).max(1.0);
+        let (dx, dy) = ((to.0 - from.0) / length * head, (to.1 - from.1) / length * head);
+        let back = (to.0 - dx, to.1 - dy);
+
+        sdl.canvas.filled_trigon(
+            to.0 as i16,
+            to.1 as i16,
+            (back.0 - dy / 2.0) as i16,
+            (back.1 + dx / 2.0) as i16,
+            (back.0 + dy / 2.0) as i16,
+            (back.1 - dx / 2.0) as i16,
+            *color
+        ).map_err(|e| anyhow!(e))?;
+    }
+
+    Ok(())
+}
+
+fn render_panel(
+    sdl: &mut SdlContext,
+    state: &GameState,
+    layout: &Layout,
+    palette: &Palette
+) -> Result<(), anyhow::Error> {
+    let buttons = layout.buttons();
+    let Some((_, number_rect)) = buttons.first() else {
+        return Ok(());
+    };
+
+    let number_font = sdl.ttf_context.load_font(
+        FONT_PATH,
+        (number_rect.height() as u16 * 2 / 3).max(1)
+    ).map_err(|e| anyhow!(e))?;
+    let tool_font = sdl.ttf_context.load_font(
+        FONT_PATH,
+        (number_rect.height() as u16 / 4).max(1)
+    ).map_err(|e| anyhow!(e))?;
+
+    for (button, rect) in &buttons {
+        let x_1 = rect.left() as i16;
+        let x_2 = rect.right() as i16;
+        let y_1 = rect.top() as i16;
+        let y_2 = rect.bottom() as i16;
+
+        if *button == Button::Notes && state.notes_mode {
+            sdl.canvas.box_(x_1, y_1, x_2, y_2, palette.selection).map_err(|e| anyhow!(e))?;
+        }
+        sdl.canvas.rectangle(x_1, y_1, x_2, y_2, palette.grid).map_err(|e| anyhow!(e))?;
+
+        let font = match button {
+            Button::Number(_) => &number_font,
+            _ => &tool_font
+        };
+        let center = rect.center();
+        print_text(
+            &mut sdl.canvas,
+            font,
+            &button.label(),
+            &palette.answer,
+            (center.x(), center.y())
+        )?;
+    }
+
+    Ok(())
+}
+
+// Numbers past 9 are shown as letters, like in hexadecimal
+fn digit_char(number: u32) -> char {
+    std::char::from_digit(number, 36)
+        .map(|c| c.to_ascii_uppercase())
+        .unwrap_or('?')
+}
+
 fn print_number(
     canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
     font: &sdl2::ttf::Font,
@@ -296,22 +770,107 @@ fn print_number(
     color: &Color,
     position: (i32, i32)
 ) -> Result<(), anyhow::Error> {
-    let c = std::char::from_digit(number.try_into()?, 10)
-        .ok_or_else(|| "wtf").map_err(|e| anyhow!(e))?;
+    print_text(canvas, font, &digit_char(number).to_string(), color, position)
+}
 
-    let (w, h) = font.size_of_char(c)?;
+fn print_text(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    font: &sdl2::ttf::Font,
+    text: &str,
+    color: &Color,
+    position: (i32, i32)
+) -> Result<(), anyhow::Error> {
+    let (w, h) = font.size_of(text)?;
 
-    let surface = font.render(&c.to_string()).blended(*color)?;
+    let surface = font.render(text).blended(*color)?;
 
     canvas.copy(
         &surface.as_texture(&canvas.texture_creator())?,
         None,
-        sdl2::rect::Rect::from_center(
-            sdl2::rect::Point::new(position.0, position.1),
-            w,
-            h
-        )
+        Rect::from_center(Point::new(position.0, position.1), w, h)
     ).map_err(|e| anyhow!(e))?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::Sandwich;
+
+    fn click(x: i32, y: i32) -> Event {
+        Event::MouseButtonDown {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mouse_btn: MouseButton::Left,
+            clicks: 1,
+            x,
+            y
+        }
+    }
+
+    #[test]
+    fn click_selects_cell() {
+        let layout = Layout::new(1060, 800, &GameState::new(Sudoku::new(9)));
+
+        assert_eq!(
+            translate_event(&click(250, 650), &Keymap::default(), &layout),
+            Some(Action::Select { row: 7, col: 2 })
+        );
+    }
+
+    #[test]
+    fn click_presses_buttons() {
+        let layout = Layout::new(1060, 800, &GameState::new(Sudoku::new(9)));
+        let buttons = layout.buttons();
+
+        assert_eq!(buttons.len(), 9 + 5);
+        for (button, rect) in &buttons {
+            assert!(layout.panel.contains_rect(*rect));
+            assert_eq!(layout.button_at(rect.center().x(), rect.center().y()), Some(*button));
+        }
+
+        let (_, five) = buttons[4];
+        let event = click(five.center().x(), five.center().y());
+        assert_eq!(
+            translate_event(&event, &Keymap::default(), &layout),
+            Some(Action::Insert { number: 5 })
+        );
+        assert_eq!(layout.button_at(1059, 799), None);
+    }
+
+    #[test]
+    fn click_on_scrolled_board() {
+        let mut sudoku = Sudoku::new(21);
+        sudoku.set_grids(9, vec![(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)]).unwrap();
+        let mut state = GameState::new(sudoku);
+        state.scroll = (1, 2);
+
+        // 19 cells of 41.8 pixels fit in the 795 pixel board
+        let layout = Layout::new(1060, 800, &state);
+        assert_eq!(layout.visible(), 19);
+        assert_eq!(layout.cell_at(20, 20), Some((1, 2)));
+        assert_eq!(layout.cell(1, 2).top_left(), Point::new(0, 0));
+
+        // the scroll can't go past the end
+        state.scroll = (9, 9);
+        let layout = Layout::new(1060, 800, &state);
+        assert_eq!(layout.cell_at(790, 790), Some((20, 20)));
+    }
+
+    #[test]
+    fn margin_for_outside_clues() {
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(Sandwich::new(9, 10, (-1, 4)).unwrap());
+
+        // 11 cells a side, the first and last for the clues
+        let layout = Layout::new(1100, 825, &GameState::new(sudoku));
+        assert_eq!(layout.cell(0, 0).top_left(), Point::new(75, 75));
+        assert_eq!(layout.point((-0.5, 4.5)), (412.5, 37.5));
+        assert_eq!(layout.cell_at(40, 100), None);
+        assert_eq!(layout.cell_at(100, 40), None);
+        assert_eq!(layout.cell_at(100, 760), None);
+        assert_eq!(layout.cell_at(100, 740), Some((8, 0)));
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::path::Path;
+use sdl2::keyboard::{Keycode, Mod};
+use crate::config;
+use crate::game::{Action, Direction};
+use crate::sudoku::Chess;
+
+// Keymap files have one binding per line, in the form
+//
+//     [Ctrl+][Shift+][Alt+]<key> = <action>
+//
+// where <action> is one of quit, delete, solve, check, generate, theme, notes,
+// undo, hint, explain, insert <n>, move up|down|left|right,
+// scroll up|down|left|right, toggle antiknight|antiking or none (to remove a
+// default binding). Lines starting with '#' are comments. Bindings in the file
+// are added on top of the default ones.
+//
+// By default S moves down like the rest of WASD, so solving is on Ctrl+S
+// only. C and G still check and generate, and so do Ctrl+C and Ctrl+G.
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("I/O Error")]
+    Io(#[from] std::io::Error),
+    #[error("Line {line}: expected '<key> = <action>'")]
+    MissingSeparator { line: usize },
+    #[error("Line {line}: unknown key {key:?}")]
+    UnknownKey { line: usize, key: String },
+    #[error("Line {line}: unknown action {action:?}")]
+    UnknownAction { line: usize, action: String }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers { ctrl: false, shift: false, alt: false };
+    pub const CTRL: Modifiers = Modifiers { ctrl: true, shift: false, alt: false };
+    pub const SHIFT: Modifiers = Modifiers { ctrl: false, shift: true, alt: false };
+
+    pub fn from_sdl(keymod: Mod) -> Modifiers {
+        Modifiers {
+            ctrl: keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD),
+            shift: keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+            alt: keymod.intersects(Mod::LALTMOD | Mod::RALTMOD)
+        }
+    }
+}
+
+pub struct Keymap {
+    bindings: HashMap<(Keycode, Modifiers), Action>
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        let mut bindings = HashMap::new();
+
+        bindings.insert((Keycode::Escape, Modifiers::NONE), Action::Quit);
+        bindings.insert((Keycode::S, Modifiers::CTRL), Action::Solve);
+        bindings.insert((Keycode::C, Modifiers::CTRL), Action::Check);
+        bindings.insert((Keycode::G, Modifiers::CTRL), Action::Generate);
+        bindings.insert((Keycode::C, Modifiers::NONE), Action::Check);
+        bindings.insert((Keycode::G, Modifiers::NONE), Action::Generate);
+        bindings.insert((Keycode::T, Modifiers::NONE), Action::NextTheme);
+        bindings.insert((Keycode::N, Modifiers::NONE), Action::ToggleNotes);
+        bindings.insert((Keycode::Z, Modifiers::CTRL), Action::Undo);
+        bindings.insert((Keycode::H, Modifiers::CTRL), Action::Hint);
+        bindings.insert((Keycode::E, Modifiers::CTRL), Action::Explain);
+        bindings.insert((Keycode::N, Modifiers::CTRL), Action::ToggleChess { rule: Chess::AntiKnight });
+        bindings.insert((Keycode::K, Modifiers::CTRL), Action::ToggleChess { rule: Chess::AntiKing });
+
+        for key in [Keycode::Delete, Keycode::Backspace, Keycode::Num0, Keycode::Kp0] {
+            bindings.insert((key, Modifiers::NONE), Action::Delete);
+        }
+
+        let digits = [
+            (Keycode::Num1, Keycode::Kp1),
+            (Keycode::Num2, Keycode::Kp2),
+            (Keycode::Num3, Keycode::Kp3),
+            (Keycode::Num4, Keycode::Kp4),
+            (Keycode::Num5, Keycode::Kp5),
+            (Keycode::Num6, Keycode::Kp6),
+            (Keycode::Num7, Keycode::Kp7),
+            (Keycode::Num8, Keycode::Kp8),
+            (Keycode::Num9, Keycode::Kp9)
+        ];
+        for (number, (key, keypad)) in (1..).zip(digits) {
+            bindings.insert((key, Modifiers::NONE), Action::Insert { number });
+            bindings.insert((keypad, Modifiers::NONE), Action::Insert { number });
+        }
+
+        // 10 to 16 for grids larger than 9
+        let letters = [
+            Keycode::A,
+            Keycode::B,
+            Keycode::C,
+            Keycode::D,
+            Keycode::E,
+            Keycode::F,
+            Keycode::G
+        ];
+        for (number, key) in (10..).zip(letters) {
+            bindings.insert((key, Modifiers::SHIFT), Action::Insert { number });
+        }
+
+        let movements = [
+            (Direction::Up, [Keycode::Up, Keycode::W, Keycode::K]),
+            (Direction::Down, [Keycode::Down, Keycode::S, Keycode::J]),
+            (Direction::Left, [Keycode::Left, Keycode::A, Keycode::H]),
+            (Direction::Right, [Keycode::Right, Keycode::D, Keycode::L])
+        ];
+        for (dir, keys) in movements {
+            for key in keys {
+                bindings.insert((key, Modifiers::NONE), Action::MoveSelection { dir });
+            }
+
+            // boards too big for the window scroll with Ctrl and the arrows
+            bindings.insert((keys[0], Modifiers::CTRL), Action::Scroll { dir });
+        }
+
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    // Loads the default keymap with the bindings of the file on top
+    pub fn from_file(path: &Path) -> Result<Keymap, Error> {
+        let text = std::fs::read_to_string(path)?;
+        let mut keymap = Keymap::default();
+
+        keymap.apply_config(&text)?;
+
+        Ok(keymap)
+    }
+
+    pub fn apply_config(&mut self, text: &str) -> Result<(), Error> {
+        for entry in config::entries(text) {
+            let (line, key, action) = entry.map_err(|line| Error::MissingSeparator { line })?;
+            let key = parse_binding(key)
+                .ok_or_else(|| Error::UnknownKey { line, key: key.to_string() })?;
+            let action = parse_action(action)
+                .ok_or_else(|| Error::UnknownAction { line, action: action.to_string() })?;
+
+            match action {
+                Some(action) => { self.bindings.insert(key, action); }
+                None => { self.bindings.remove(&key); }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn lookup(&self, key: Keycode, modifiers: Modifiers) -> Option<Action> {
+        self.bindings.get(&(key, modifiers)).copied()
+    }
+}
+
+fn parse_binding(text: &str) -> Option<(Keycode, Modifiers)> {
+    let mut modifiers = Modifiers::NONE;
+    let mut parts: Vec<_> = text.split('+').map(str::trim).collect();
+    let key = parse_key(parts.pop()?)?;
+
+    for part in parts {
+        match part.to_lowercase().as_str() {
+            "ctrl" | "control" => modifiers.ctrl = true,
+            "shift" => modifiers.shift = true,
+            "alt" => modifiers.alt = true,
+            _ => return None
+        }
+    }
+
+    Some((key, modifiers))
+}
+
+fn parse_key(name: &str) -> Option<Keycode> {
+    let name = name.to_lowercase();
+
+    // letters and digits have their ascii value as keycode
+    if let [c @ (b'a'..=b'z' | b'0'..=b'9')] = name.as_bytes() {
+        return Keycode::from_i32(*c as i32);
+    }
+
+    let key = match name.as_str() {
+        "escape" | "esc" => Keycode::Escape,
+        "delete" | "del" => Keycode::Delete,
+        "backspace" => Keycode::Backspace,
+        "return" | "enter" => Keycode::Return,
+        "space" => Keycode::Space,
+        "tab" => Keycode::Tab,
+        "up" => Keycode::Up,
+        "down" => Keycode::Down,
+        "left" => Keycode::Left,
+        "right" => Keycode::Right,
+        "kp0" => Keycode::Kp0,
+        "kp1" => Keycode::Kp1,
+        "kp2" => Keycode::Kp2,
+        "kp3" => Keycode::Kp3,
+        "kp4" => Keycode::Kp4,
+        "kp5" => Keycode::Kp5,
+        "kp6" => Keycode::Kp6,
+        "kp7" => Keycode::Kp7,
+        "kp8" => Keycode::Kp8,
+        "kp9" => Keycode::Kp9,
+        "f1" => Keycode::F1,
+        "f2" => Keycode::F2,
+        "f3" => Keycode::F3,
+        "f4" => Keycode::F4,
+        "f5" => Keycode::F5,
+        "f6" => Keycode::F6,
+        "f7" => Keycode::F7,
+        "f8" => Keycode::F8,
+        "f9" => Keycode::F9,
+        "f10" => Keycode::F10,
+        "f11" => Keycode::F11,
+        "f12" => Keycode::F12,
+        _ => return None
+    };
+
+    Some(key)
+}
+
+// Returns Some(None) for "none", which unbinds the key
+fn parse_action(text: &str) -> Option<Option<Action>> {
+    let text = text.to_lowercase();
+    let words: Vec<_> = text.split_whitespace().collect();
+
+    let action = match words.as_slice() {
+        ["none"] => return Some(None),
+        ["quit"] => Action::Quit,
+        ["delete"] => Action::Delete,
+        ["solve"] => Action::Solve,
+        ["check"] => Action::Check,
+        ["generate"] => Action::Generate,
+        ["theme"] => Action::NextTheme,
+        ["notes"] => Action::ToggleNotes,
+        ["undo"] => Action::Undo,
+        ["hint"] => Action::Hint,
+        ["explain"] => Action::Explain,
+        ["insert", number] => Action::Insert { number: number.parse().ok()? },
+        ["move", dir] => Action::MoveSelection { dir: parse_direction(dir)? },
+        ["scroll", dir] => Action::Scroll { dir: parse_direction(dir)? },
+        ["toggle", rule] => Action::ToggleChess { rule: Chess::from_name(rule)? },
+        _ => return None
+    };
+
+    Some(Some(action))
+}
+
+fn parse_direction(text: &str) -> Option<Direction> {
+    match text {
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_bindings() {
+        let keymap = Keymap::default();
+
+        assert_eq!(
+            keymap.lookup(Keycode::Num7, Modifiers::NONE),
+            Some(Action::Insert { number: 7 })
+        );
+        assert_eq!(
+            keymap.lookup(Keycode::B, Modifiers::SHIFT),
+            Some(Action::Insert { number: 11 })
+        );
+        assert_eq!(keymap.lookup(Keycode::Num0, Modifiers::NONE), Some(Action::Delete));
+        assert_eq!(keymap.lookup(Keycode::S, Modifiers::CTRL), Some(Action::Solve));
+        assert_eq!(keymap.lookup(Keycode::C, Modifiers::NONE), Some(Action::Check));
+        assert_eq!(keymap.lookup(Keycode::G, Modifiers::NONE), Some(Action::Generate));
+        assert_eq!(
+            keymap.lookup(Keycode::S, Modifiers::NONE),
+            Some(Action::MoveSelection { dir: Direction::Down })
+        );
+        assert_eq!(keymap.lookup(Keycode::Num7, Modifiers::CTRL), None);
+    }
+
+    #[test]
+    fn config_overrides_defaults() -> Result<(), anyhow::Error> {
+        let mut keymap = Keymap::default();
+
+        keymap.apply_config("
+            # solve with F5 only
+            F5 = solve
+            ctrl+s = none
+            Ctrl+Shift+X = insert 12
+            alt+k = toggle antiking
+        ")?;
+
+        assert_eq!(keymap.lookup(Keycode::F5, Modifiers::NONE), Some(Action::Solve));
+        assert_eq!(keymap.lookup(Keycode::S, Modifiers::CTRL), None);
+        assert_eq!(
+            keymap.lookup(Keycode::X, Modifiers { ctrl: true, shift: true, alt: false }),
+            Some(Action::Insert { number: 12 })
+        );
+        assert_eq!(
+            keymap.lookup(Keycode::K, Modifiers { ctrl: false, shift: false, alt: true }),
+            Some(Action::ToggleChess { rule: Chess::AntiKing })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn config_errors() {
+        let mut keymap = Keymap::default();
+
+        assert!(matches!(
+            keymap.apply_config("S solve"),
+            Err(Error::MissingSeparator { line: 1 })
+        ));
+        assert!(matches!(
+            keymap.apply_config("\nHyper+S = solve"),
+            Err(Error::UnknownKey { line: 2, .. })
+        ));
+        assert!(matches!(
+            keymap.apply_config("S = fly"),
+            Err(Error::UnknownAction { line: 1, .. })
+        ));
+    }
+}
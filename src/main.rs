@@ -1,10 +1,26 @@
 mod interface;
 mod sudoku;
 mod game;
+mod config;
+mod keymap;
+mod palette;
+mod settings;
+mod cli;
+mod bench;
 
 fn main() {
-    if let Err(e) = game::run() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = if args.is_empty() {
+        game::run()
+    } else {
+        cli::run(&args).map_err(anyhow::Error::from)
+    };
+
+    // so scripts can tell when a puzzle, a model or the benchmark went wrong
+    if let Err(e) = result {
         eprintln!("{}", e);
+        std::process::exit(1);
     }
 }
 
@@ -0,0 +1,298 @@
+use std::path::Path;
+use sdl2::pixels::Color;
+use crate::config;
+use crate::sudoku::Ink;
+
+// Palette files override the colors of the dark theme, one per line, as
+//
+//     <name> = <r>, <g>, <b>
+//
+// or with a hex value like `peer = #1e1e3c`.
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("I/O Error")]
+    Io(#[from] std::io::Error),
+    #[error("Line {line}: expected '<name> = <color>'")]
+    MissingSeparator { line: usize },
+    #[error("Line {line}: unknown color name {name:?}")]
+    UnknownName { line: usize, name: String },
+    #[error("Line {line}: invalid color {value:?}")]
+    InvalidColor { line: usize, value: String }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    pub background: Color,
+    pub grid: Color,
+    pub given: Color,
+    pub answer: Color,
+    pub error: Color,
+    pub selection: Color,
+    // row, column and box of the selected cell
+    pub peer: Color,
+    // cells with the same digit as the selected one
+    pub same_digit: Color,
+    // killer cage outlines and sums
+    pub cage: Color,
+    // lines across the board when the diagonals are a rule
+    pub diagonal: Color,
+    // shade of the cells in extra houses
+    pub house: Color,
+    // thermometers and arrows, drawn under the numbers
+    pub clue: Color
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette::DARK
+    }
+}
+
+impl Palette {
+    pub const DARK: Palette = Palette {
+        background: Color::RGB(0, 0, 0),
+        grid: Color::WHITE,
+        given: Color::GREY,
+        answer: Color::WHITE,
+        error: Color::RGB(100, 0, 0),
+        selection: Color::RGB(30, 30, 220),
+        peer: Color::RGB(25, 25, 50),
+        same_digit: Color::RGB(30, 60, 100),
+        cage: Color::RGB(200, 200, 120),
+        diagonal: Color::RGB(60, 60, 60),
+        house: Color::RGB(35, 35, 35),
+        clue: Color::RGB(80, 80, 80)
+    };
+
+    pub const LIGHT: Palette = Palette {
+        background: Color::RGB(250, 250, 250),
+        grid: Color::RGB(20, 20, 20),
+        given: Color::RGB(20, 20, 20),
+        answer: Color::RGB(30, 70, 170),
+        error: Color::RGB(255, 170, 170),
+        selection: Color::RGB(40, 110, 230),
+        peer: Color::RGB(228, 232, 242),
+        same_digit: Color::RGB(190, 212, 245),
+        cage: Color::RGB(90, 90, 90),
+        diagonal: Color::RGB(205, 205, 205),
+        house: Color::RGB(232, 232, 232),
+        clue: Color::RGB(195, 195, 195)
+    };
+
+    pub const HIGH_CONTRAST: Palette = Palette {
+        background: Color::RGB(0, 0, 0),
+        grid: Color::WHITE,
+        given: Color::WHITE,
+        answer: Color::RGB(255, 255, 0),
+        error: Color::RGB(190, 0, 0),
+        selection: Color::RGB(0, 255, 255),
+        peer: Color::RGB(55, 55, 55),
+        same_digit: Color::RGB(0, 0, 150),
+        cage: Color::RGB(255, 255, 255),
+        diagonal: Color::RGB(120, 120, 120),
+        house: Color::RGB(70, 70, 70),
+        clue: Color::RGB(150, 150, 150)
+    };
+
+    // Okabe-Ito colors, which stay apart for every kind of color blindness
+    pub const COLOR_BLIND: Palette = Palette {
+        background: Color::RGB(15, 15, 15),
+        grid: Color::RGB(230, 230, 230),
+        given: Color::RGB(200, 200, 200),
+        answer: Color::RGB(86, 180, 233),
+        error: Color::RGB(213, 94, 0),
+        selection: Color::RGB(240, 228, 66),
+        peer: Color::RGB(45, 45, 45),
+        same_digit: Color::RGB(0, 80, 125),
+        cage: Color::RGB(204, 121, 167),
+        diagonal: Color::RGB(70, 70, 70),
+        house: Color::RGB(40, 40, 40),
+        clue: Color::RGB(90, 90, 90)
+    };
+
+    pub fn named(name: &str) -> Option<Palette> {
+        match name {
+            "dark" => Some(Palette::DARK),
+            "light" => Some(Palette::LIGHT),
+            "high-contrast" => Some(Palette::HIGH_CONTRAST),
+            "color-blind" => Some(Palette::COLOR_BLIND),
+            _ => None
+        }
+    }
+
+    // Loads the default palette with the colors of the file on top
+    pub fn from_file(path: &Path) -> Result<Palette, Error> {
+        let text = std::fs::read_to_string(path)?;
+        let mut palette = Palette::default();
+
+        palette.apply_config(&text)?;
+
+        Ok(palette)
+    }
+
+    pub fn apply_config(&mut self, text: &str) -> Result<(), Error> {
+        for entry in config::entries(text) {
+            let (line, name, value) = entry.map_err(|line| Error::MissingSeparator { line })?;
+            let color = parse_color(value)
+                .ok_or_else(|| Error::InvalidColor { line, value: value.to_string() })?;
+            let field = self.field_mut(name)
+                .ok_or_else(|| Error::UnknownName { line, name: name.to_string() })?;
+
+            *field = color;
+        }
+
+        Ok(())
+    }
+
+    pub fn ink(&self, ink: Ink) -> Color {
+        match ink {
+            Ink::Grid => self.grid,
+            Ink::Background => self.background,
+            Ink::Cage => self.cage,
+            Ink::Diagonal => self.diagonal,
+            Ink::House => self.house,
+            Ink::Given => self.given,
+            Ink::Clue => self.clue
+        }
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut Color> {
+        match name {
+            "background" => Some(&mut self.background),
+            "grid" => Some(&mut self.grid),
+            "given" => Some(&mut self.given),
+            "answer" => Some(&mut self.answer),
+            "error" => Some(&mut self.error),
+            "selection" => Some(&mut self.selection),
+            "peer" => Some(&mut self.peer),
+            "same_digit" => Some(&mut self.same_digit),
+            "cage" => Some(&mut self.cage),
+            "diagonal" => Some(&mut self.diagonal),
+            "house" => Some(&mut self.house),
+            "clue" => Some(&mut self.clue),
+            _ => None
+        }
+    }
+}
+
+fn parse_color(text: &str) -> Option<Color> {
+    if let Some(hex) = text.strip_prefix('#') {
+        // from_str_radix would also take a sign
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let value = u32::from_str_radix(hex, 16).ok()?;
+
+        return Some(Color::RGB((value >> 16) as u8, (value >> 8) as u8, value as u8));
+    }
+
+    let channels: Vec<u8> = text.split(',')
+        .map(|c| c.trim().parse())
+        .collect::<Result<_, _>>()
+        .ok()?;
+
+    match channels.as_slice() {
+        [r, g, b] => Some(Color::RGB(*r, *g, *b)),
+        _ => None
+    }
+}
+
+pub const THEMES: [&str; 4] = ["dark", "light", "high-contrast", "color-blind"];
+
+// The palettes the player can cycle through at runtime
+pub struct Themes {
+    themes: Vec<(String, Palette)>,
+    current: usize
+}
+
+impl Themes {
+    // Built-in themes, plus a "custom" one when there is a custom palette
+    pub fn new(custom: Option<Palette>) -> Themes {
+        let mut themes: Vec<_> = THEMES.iter()
+            .filter_map(|name| Palette::named(name).map(|p| (name.to_string(), p)))
+            .collect();
+
+        if let Some(palette) = custom {
+            themes.push(("custom".to_string(), palette));
+        }
+
+        Themes { themes, current: 0 }
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.themes[self.current].1
+    }
+
+    pub fn name(&self) -> &str {
+        &self.themes[self.current].0
+    }
+
+    // Returns false if there is no theme with that name
+    pub fn select(&mut self, name: &str) -> bool {
+        match self.themes.iter().position(|(n, _)| n == name) {
+            Some(i) => {
+                self.current = i;
+                true
+            }
+            None => false
+        }
+    }
+
+    pub fn next(&mut self) {
+        self.current = (self.current + 1) % self.themes.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_overrides_defaults() -> Result<(), anyhow::Error> {
+        let mut palette = Palette::default();
+
+        palette.apply_config("peer = 1, 2, 3\nsame_digit = #0a0b0c")?;
+
+        assert_eq!(palette.peer, Color::RGB(1, 2, 3));
+        assert_eq!(palette.same_digit, Color::RGB(10, 11, 12));
+        assert_eq!(palette.grid, Palette::default().grid);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cycle_themes() {
+        let mut themes = Themes::new(Some(Palette::LIGHT));
+
+        assert_eq!(themes.name(), "dark");
+        assert!(themes.select("color-blind"));
+        assert_eq!(themes.palette(), &Palette::COLOR_BLIND);
+        assert!(!themes.select("sepia"));
+        assert_eq!(themes.name(), "color-blind");
+
+        themes.next();
+        assert_eq!(themes.name(), "custom");
+        themes.next();
+        assert_eq!(themes.name(), "dark");
+    }
+
+    #[test]
+    fn config_errors() {
+        let mut palette = Palette::default();
+
+        assert!(matches!(
+            palette.apply_config("peer = 1, 2"),
+            Err(Error::InvalidColor { line: 1, .. })
+        ));
+        assert!(matches!(
+            palette.apply_config("peer = #+12345"),
+            Err(Error::InvalidColor { line: 1, .. })
+        ));
+        assert!(matches!(
+            palette.apply_config("paper = 1, 2, 3"),
+            Err(Error::UnknownName { line: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,113 @@
+use std::path::Path;
+use crate::config;
+
+// Preferences that are saved between sessions, stored as `key = value` lines
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("I/O Error")]
+    Io(#[from] std::io::Error),
+    #[error("Line {line}: expected '<setting> = <value>'")]
+    MissingSeparator { line: usize },
+    #[error("Line {line}: unknown setting {name:?}")]
+    UnknownSetting { line: usize, name: String }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settings {
+    pub theme: String,
+    // the settings this version doesn't know, written back as they were
+    unknown: Vec<(String, String)>
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings { theme: "dark".to_string(), unknown: Vec::new() }
+    }
+}
+
+impl Settings {
+    pub fn from_file(path: &Path) -> Result<(Settings, Vec<Error>), Error> {
+        let text = std::fs::read_to_string(path)?;
+
+        Settings::parse(&text)
+    }
+
+    // The settings, and the unknown ones that were skipped, maybe from a newer
+    // or older version, to warn about
+    pub fn parse(text: &str) -> Result<(Settings, Vec<Error>), Error> {
+        let mut settings = Settings::default();
+        let mut skipped = Vec::new();
+
+        for entry in config::entries(text) {
+            let (line, name, value) = entry.map_err(|line| Error::MissingSeparator { line })?;
+
+            match name {
+                "theme" => settings.theme = value.to_string(),
+                _ => {
+                    settings.unknown.push((name.to_string(), value.to_string()));
+                    skipped.push(Error::UnknownSetting { line, name: name.to_string() });
+                }
+            }
+        }
+
+        Ok((settings, skipped))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        std::fs::write(path, self.to_string())?;
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Settings {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "theme = {}", self.theme)?;
+
+        for (name, value) in &self.unknown {
+            writeln!(f, "{} = {}", name, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() -> Result<(), anyhow::Error> {
+        let settings = Settings { theme: "high-contrast".to_string(), ..Settings::default() };
+
+        let (read, skipped) = Settings::parse(&settings.to_string())?;
+        assert_eq!(read, settings);
+        assert!(skipped.is_empty());
+
+        let (read, skipped) = Settings::parse("volume = 11\ntheme = high-contrast")?;
+        assert_eq!(read.theme, settings.theme);
+        assert!(matches!(skipped[..], [Error::UnknownSetting { line: 1, .. }]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn saving_keeps_unknown_settings() -> Result<(), anyhow::Error> {
+        let path = std::env::temp_dir().join(format!("sudoku-settings-{}.cfg", std::process::id()));
+        std::fs::write(&path, "volume = 11\ntheme = light\n")?;
+
+        let (mut settings, _) = Settings::from_file(&path)?;
+        settings.theme = "dark".to_string();
+        settings.save(&path)?;
+
+        let (read, skipped) = Settings::from_file(&path)?;
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(read, settings);
+        assert_eq!(read.theme, "dark");
+        assert!(matches!(&skipped[..], [Error::UnknownSetting { name, .. }] if name == "volume"));
+
+        Ok(())
+    }
+}
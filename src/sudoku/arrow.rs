@@ -0,0 +1,170 @@
+use std::collections::hash_set::HashSet;
+
+use crate::sudoku::*;
+
+// The number in the circle is the sum of the numbers along the arrow, which can
+// repeat unless they share a house
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Arrow {
+    pub circle: (usize, usize),
+    // (row, col), from the circle to the tip
+    pub cells: Vec<(usize, usize)>
+}
+
+impl Arrow {
+    pub fn new(circle: (usize, usize), cells: Vec<(usize, usize)>) -> Arrow {
+        Arrow { circle, cells }
+    }
+}
+
+impl Constraint for Arrow {
+    fn check(&self, sudoku: &Sudoku, wrong_numbers: &mut HashSet<(usize, usize)>) {
+        let (sum, empty) = filled(sudoku, &self.cells);
+        // the empty cells need at least a 1 each
+        let least = sum + empty;
+
+        let wrong = match sudoku.check_position(self.circle.0, self.circle.1).value() {
+            Some(circle) => least > circle as usize || (empty == 0 && sum != circle as usize),
+            None => least > sudoku.digits()
+        };
+
+        if wrong {
+            wrong_numbers.insert(self.circle);
+            wrong_numbers.extend(self.cells.iter().copied());
+        }
+    }
+
+    fn prune(&self, sudoku: &Sudoku, row: usize, col: usize, candidates: &mut HashSet<u8>) {
+        let (sum, empty) = filled(sudoku, &self.cells);
+        let digits = sudoku.digits();
+
+        if (row, col) == self.circle {
+            let most = sum + empty * digits;
+            candidates.retain(|&n| (sum + empty..=most).contains(&(n as usize)));
+
+            return;
+        }
+
+        if !self.cells.contains(&(row, col)) {
+            return;
+        }
+
+        // without a number in the circle the most it can have
+        let circle = sudoku.check_position(self.circle.0, self.circle.1).value();
+        let room = room(circle.map_or(digits, |n| n as usize), sum, empty);
+
+        candidates.retain(|&n| {
+            let n = n as usize;
+            n <= room && (empty > 1 || circle.is_none() || n == room)
+        });
+    }
+
+    // The circle first, then what's left of it after each number of the arrow
+    fn encode(&self, _sudoku: &Sudoku, cnf: &mut Cnf) {
+        let cells: Vec<_> = std::iter::once(self.circle).chain(self.cells.iter().copied()).collect();
+
+        cnf.automaton(
+            &cells,
+            None,
+            |left: &Option<u8>, n| match left {
+                None => Some(Some(n)),
+                Some(left) => left.checked_sub(n).map(Some)
+            },
+            |&left| left == Some(0)
+        );
+    }
+
+    fn shapes(&self, _size: usize) -> Vec<Shape> {
+        let center = |(row, col): (usize, usize)| (row as f32 + 0.5, col as f32 + 0.5);
+        let line = |from, to| Shape::Line { from, to, width: 0.05, ink: Ink::Clue };
+
+        let mut shapes = vec![
+            Shape::Circle { center: center(self.circle), radius: 0.4, filled: false, ink: Ink::Clue }
+        ];
+
+        let mut points: Vec<_> = std::iter::once(self.circle).chain(self.cells.iter().copied())
+            .map(center)
+            .collect();
+
+        if points.len() < 2 {
+            return shapes;
+        }
+
+        // start at the edge of the circle
+        let (row, col) = points[0];
+        let (d_row, d_col) = (points[1].0 - row, points[1].1 - col);
+        let length = d_row.hypot(d_col);
+        points[0] = (row + d_row / length * 0.4, col + d_col / length * 0.4);
+
+        shapes.extend(points.windows(2).map(|pair| line(pair[0], pair[1])));
+
+        // head pointing the way of the last segment
+        let (tip, before) = (points[points.len() - 1], points[points.len() - 2]);
+        let (d_row, d_col) = (tip.0 - before.0, tip.1 - before.1);
+        let length = d_row.hypot(d_col);
+        let (d_row, d_col) = (d_row / length * 0.25, d_col / length * 0.25);
+
+        shapes.push(line(tip, (tip.0 - d_row - d_col * 0.6, tip.1 - d_col + d_row * 0.6)));
+        shapes.push(line(tip, (tip.0 - d_row + d_col * 0.6, tip.1 - d_col - d_row * 0.6)));
+
+        shapes
+    }
+
+    fn regenerate(&self) -> Regenerate {
+        Regenerate::Arrow
+    }
+
+    fn name(&self) -> &'static str {
+        "arrow"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrow_violations() {
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(Arrow::new((0, 0), vec![(1, 1), (2, 2), (3, 3)]));
+
+        sudoku.insert_number(0, 0, 9);
+        sudoku.insert_number(1, 1, 5);
+        assert!(sudoku.check_rules().is_empty());
+
+        // 5 and two more cells can't add up to 6
+        sudoku.insert_number(0, 0, 6);
+        assert_eq!(sudoku.check_rules(), HashSet::from([(0, 0), (1, 1), (2, 2), (3, 3)]));
+
+        // full, but adds up to 8
+        sudoku.insert_number(0, 0, 9);
+        sudoku.insert_number(2, 2, 2);
+        sudoku.insert_number(3, 3, 1);
+        assert_eq!(sudoku.check_rules(), HashSet::from([(0, 0), (1, 1), (2, 2), (3, 3)]));
+
+        sudoku.insert_number(3, 3, 2);
+        assert!(sudoku.check_rules().is_empty());
+    }
+
+    #[test]
+    fn arrow_candidates() {
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(Arrow::new((0, 0), vec![(0, 1), (0, 2)]));
+        sudoku.add_constraint(Arrow::new((8, 8), vec![(7, 7), (6, 6)]));
+        sudoku.insert_given(0, 1, 3);
+        sudoku.insert_given(8, 8, 7);
+        sudoku.insert_given(7, 7, 4);
+
+        assert_eq!(pruned(&sudoku, 0, 0), HashSet::from_iter(4..=9));
+        assert_eq!(pruned(&sudoku, 0, 2), HashSet::from_iter(1..=6));
+        assert_eq!(pruned(&sudoku, 6, 6), HashSet::from([3]));
+    }
+
+    #[test]
+    fn solve_arrows() -> Result<(), anyhow::Error> {
+        let sudoku = solves("tests/arrow")?;
+        assert!(sudoku.constraints().iter().any(|c| c.name() == "arrow"));
+
+        Ok(())
+    }
+}
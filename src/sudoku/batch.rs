@@ -0,0 +1,145 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::sudoku::*;
+
+// What a batch finds out about every puzzle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    // the solution, and whether it's the only one
+    Solve,
+    // that, and how hard the puzzle is for a person
+    Rate
+}
+
+// The hardest technique a puzzle needs, or guessing when they get stuck
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rating {
+    Logic(Technique),
+    Guessing
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    pub solution: Option<Sudoku>,
+    // how many solutions there are, stopping at 2
+    pub count: usize,
+    // only when rating, and not for puzzles without a solution
+    pub rating: Option<Rating>,
+    pub time: Duration
+}
+
+impl Sudoku {
+    // Steps with every technique until the puzzle is done or they get stuck.
+    // The ones that need a single solution are only used if `unique`.
+    pub fn rate(&self, unique: bool) -> Rating {
+        let mut sudoku = self.clone();
+        let mut candidates = Candidates::new(&sudoku);
+        candidates.set_unique(unique);
+
+        let hardest = candidates.propagate(&mut sudoku, Technique::HARDEST).into_iter()
+            .map(|deduction| deduction.technique)
+            .max();
+
+        if sudoku.iterate().any(|(_, _, n)| *n == Number::Empty) {
+            return Rating::Guessing;
+        }
+
+        Rating::Logic(hardest.unwrap_or(Technique::NakedSingle))
+    }
+
+    fn report(&self, mode: Mode) -> Report {
+        let now = Instant::now();
+        let mut solutions = Vec::new();
+
+        self.clone().search(2, &mut |_| {}, &mut solutions);
+
+        let count = solutions.len();
+        let rating = (mode == Mode::Rate && count > 0).then(|| self.rate(count == 1));
+
+        Report { solution: solutions.into_iter().next(), count, rating, time: now.elapsed() }
+    }
+}
+
+// Reads a file with a puzzle a line (see `Sudoku::from_line`), skipping blank
+// lines and the ones starting with '#'
+pub fn read_puzzles(path: &Path) -> Result<Vec<Sudoku>, Error> {
+    let text = std::fs::read_to_string(path)?;
+
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| Sudoku::from_line(line).ok_or(Error::InvalidLine { line: i, text: line.to_string() }))
+        .collect()
+}
+
+// Works on the puzzles in every core at once, each thread taking the next
+// puzzle nobody has taken yet, and returns the reports in the same order
+pub fn run_batch(puzzles: &[Sudoku], mode: Mode) -> Vec<Report> {
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(puzzles.len())
+        .max(1);
+    let next = AtomicUsize::new(0);
+
+    let mut reports: Vec<(usize, Report)> = std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..workers)
+            .map(|_| scope.spawn(|| {
+                let mut done = Vec::new();
+
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(puzzle) = puzzles.get(i) else {
+                        break;
+                    };
+
+                    done.push((i, puzzle.report(mode)));
+                }
+
+                done
+            }))
+            .collect();
+
+        threads.into_iter()
+            .flat_map(|thread| thread.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    });
+
+    reports.sort_by_key(|&(i, _)| i);
+    reports.into_iter().map(|(_, report)| report).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_keeps_the_order() -> Result<(), anyhow::Error> {
+        let puzzles = read_puzzles(Path::new("tests/batch"))?;
+        assert_eq!(puzzles.len(), 5);
+        assert_eq!(Sudoku::from_line(&puzzles[0].to_line()), Some(puzzles[0].clone()));
+
+        let reports = run_batch(&puzzles, Mode::Rate);
+        let counts: Vec<usize> = reports.iter().map(|report| report.count).collect();
+        assert_eq!(counts, vec![1, 1, 2, 0, 1]);
+
+        for (puzzle, report) in puzzles.iter().zip(&reports) {
+            if let Some(solution) = &report.solution {
+                assert!(solution.check_rules().is_empty());
+                assert!(puzzle.iterate().all(|(row, col, n)| n.compare(solution.check_position(row, col)) || n.value().is_none()));
+            }
+        }
+
+        assert!(matches!(reports[0].rating, Some(Rating::Logic(technique)) if technique > Technique::HiddenSingle));
+        assert_eq!(reports[1].rating, Some(Rating::Guessing));
+        assert_eq!(reports[3].rating, None);
+        assert_eq!(reports[4].rating, Some(Rating::Logic(Technique::NakedSingle)));
+
+        let reports = run_batch(&puzzles, Mode::Solve);
+        assert!(reports.iter().all(|report| report.rating.is_none()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,465 @@
+use std::collections::hash_map::HashMap;
+use std::collections::VecDeque;
+
+use crate::sudoku::*;
+use crate::sudoku::logic::{Deduction, Technique};
+
+// (row, col, number)
+type Candidate = (usize, usize, u8);
+
+// Which links a chain can go through
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chain {
+    // between places of the same number only
+    X,
+    // strong links inside cells with two numbers, weak ones between cells
+    XY,
+    // any of them
+    Alternating
+}
+
+// Chains longer than this many candidates aren't followed
+const LONGEST: usize = 12;
+
+// Whether two different cells share a house
+pub(super) fn sees(sudoku: &Sudoku, (row, col): (usize, usize), (other_row, other_col): (usize, usize)) -> bool {
+    (row, col) != (other_row, other_col)
+        && sudoku.houses_of(row, col).iter().any(|house| sudoku.houses_of(other_row, other_col).contains(house))
+}
+
+impl Candidates {
+    fn empty_cells(&self, sudoku: &Sudoku) -> Vec<(usize, usize)> {
+        sudoku.iterate()
+            .filter(|(_, _, n)| **n == Number::Empty)
+            .map(|(row, col, _)| (row, col))
+            .collect()
+    }
+
+    // The two numbers of a cell that only has two, smallest first
+    fn pair(&self, (row, col): (usize, usize)) -> Option<(u8, u8)> {
+        match self.get(row, col).iter().collect::<Vec<_>>()[..] {
+            [&a, &b] => Some((a.min(b), a.max(b))),
+            _ => None
+        }
+    }
+
+    // The places for the number in cells that see every one of `cells`
+    fn seen_by_all(&self, sudoku: &Sudoku, cells: &[(usize, usize)], n: u8) -> Vec<Candidate> {
+        self.empty_cells(sudoku).into_iter()
+            .filter(|&(row, col)| self.get(row, col).contains(&n))
+            .filter(|&cell| cells.iter().all(|&other| sees(sudoku, cell, other)))
+            .map(|(row, col)| (row, col, n))
+            .collect()
+    }
+
+    fn wing(
+        &mut self,
+        technique: Technique,
+        cells: Vec<(usize, usize)>,
+        digits: Vec<u8>,
+        targets: Vec<Candidate>,
+        chain: Vec<Candidate>
+    ) -> Option<Deduction> {
+        let eliminations = self.eliminate(targets);
+
+        (!eliminations.is_empty()).then_some(Deduction { technique, houses: Vec::new(), cells, digits, eliminations, chain })
+    }
+
+    // A pivot with x and y, a pincer with x and z and another with y and z,
+    // both seeing the pivot: one of the pincers has z either way
+    pub(super) fn xy_wing(&mut self, sudoku: &Sudoku) -> Option<Deduction> {
+        let pairs: Vec<_> = self.empty_cells(sudoku).into_iter()
+            .filter_map(|cell| Some((cell, self.pair(cell)?)))
+            .collect();
+
+        for &(pivot, (x, y)) in &pairs {
+            for &(a, a_pair) in &pairs {
+                let z = match a_pair {
+                    (p, q) if p == x && q != y => q,
+                    (p, q) if q == x && p != y => p,
+                    _ => continue
+                };
+
+                if !sees(sudoku, pivot, a) {
+                    continue;
+                }
+
+                for &(b, b_pair) in &pairs {
+                    if b_pair != (y.min(z), y.max(z)) || !sees(sudoku, pivot, b) || a == b {
+                        continue;
+                    }
+
+                    let targets = self.seen_by_all(sudoku, &[a, b], z);
+                    let chain = vec![
+                        (a.0, a.1, z), (a.0, a.1, x),
+                        (pivot.0, pivot.1, x), (pivot.0, pivot.1, y),
+                        (b.0, b.1, y), (b.0, b.1, z)
+                    ];
+
+                    if let Some(deduction) = self.wing(Technique::XYWing, vec![a, pivot, b], vec![x, y, z], targets, chain) {
+                        return Some(deduction);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    // Like the XY-Wing, with z in the pivot as well
+    pub(super) fn xyz_wing(&mut self, sudoku: &Sudoku) -> Option<Deduction> {
+        let cells = self.empty_cells(sudoku);
+        let pairs: Vec<_> = cells.iter().copied()
+            .filter_map(|cell| Some((cell, self.pair(cell)?)))
+            .collect();
+
+        for &pivot in &cells {
+            let numbers = self.get(pivot.0, pivot.1).clone();
+
+            if numbers.len() != 3 {
+                continue;
+            }
+
+            let pincers: Vec<_> = pairs.iter()
+                .filter(|&&(cell, (p, q))| sees(sudoku, pivot, cell) && numbers.contains(&p) && numbers.contains(&q))
+                .collect();
+
+            for (i, &&(a, (p, q))) in pincers.iter().enumerate() {
+                for &&(b, (r, s)) in &pincers[i + 1..] {
+                    // one number in common, and the other two are different
+                    let z = match (p, q) {
+                        _ if (p, q) == (r, s) => continue,
+                        _ if p == r || p == s => p,
+                        _ if q == r || q == s => q,
+                        _ => continue
+                    };
+
+                    let mut digits: Vec<u8> = numbers.iter().copied().collect();
+                    digits.sort_unstable();
+
+                    let targets = self.seen_by_all(sudoku, &[a, pivot, b], z);
+
+                    if let Some(deduction) = self.wing(Technique::XYZWing, vec![a, pivot, b], digits, targets, Vec::new()) {
+                        return Some(deduction);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    // Two cells with x and y that don't see each other, and a house where x
+    // only has two places, one seeing each cell: one of the cells has y
+    pub(super) fn w_wing(&mut self, sudoku: &Sudoku) -> Option<Deduction> {
+        let pairs: Vec<_> = self.empty_cells(sudoku).into_iter()
+            .filter_map(|cell| Some((cell, self.pair(cell)?)))
+            .collect();
+
+        for (i, &(a, pair)) in pairs.iter().enumerate() {
+            for &(b, other) in &pairs[i + 1..] {
+                if pair != other || sees(sudoku, a, b) {
+                    continue;
+                }
+
+                for (x, y) in [pair, (pair.1, pair.0)] {
+                    for house in sudoku.houses() {
+                        let Some(&[p, q]) = self.places(sudoku, house, x).as_deref() else {
+                            continue;
+                        };
+
+                        let (p, q) = match (p, q) {
+                            _ if [p, q].contains(&a) || [p, q].contains(&b) => continue,
+                            _ if sees(sudoku, p, a) && sees(sudoku, q, b) => (p, q),
+                            _ if sees(sudoku, q, a) && sees(sudoku, p, b) => (q, p),
+                            _ => continue
+                        };
+
+                        let targets = self.seen_by_all(sudoku, &[a, b], y);
+                        let chain = vec![
+                            (a.0, a.1, y), (a.0, a.1, x),
+                            (p.0, p.1, x), (q.0, q.1, x),
+                            (b.0, b.1, x), (b.0, b.1, y)
+                        ];
+
+                        if let Some(deduction) = self.wing(Technique::WWing, vec![a, b], vec![x, y], targets, chain) {
+                            return Some(deduction);
+                        }
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    // Candidates where, if this one is false, the other is true
+    fn strong_links(&self, sudoku: &Sudoku, kind: Chain, (row, col, n): Candidate) -> Vec<Candidate> {
+        let mut links = Vec::new();
+
+        if kind != Chain::X {
+            if let Some((a, b)) = self.pair((row, col)) {
+                links.push((row, col, if a == n { b } else { a }));
+            }
+        }
+
+        if kind != Chain::XY {
+            for &house in sudoku.houses_of(row, col) {
+                if let Some(&[p, q]) = self.places(sudoku, &sudoku.houses()[house], n).as_deref() {
+                    let (i, j) = if p == (row, col) { q } else { p };
+                    links.push((i, j, n));
+                }
+            }
+        }
+
+        links
+    }
+
+    // Candidates where, if this one is true, the other is false
+    fn weak_links(&self, sudoku: &Sudoku, kind: Chain, (row, col, n): Candidate) -> Vec<Candidate> {
+        let mut links = Vec::new();
+
+        if kind == Chain::Alternating {
+            links.extend(self.get(row, col).iter().filter(|&&m| m != n).map(|&m| (row, col, m)));
+        }
+
+        for &house in sudoku.houses_of(row, col) {
+            links.extend(sudoku.houses()[house].iter()
+                .filter(|&&(i, j)| (i, j) != (row, col) && self.get(i, j).contains(&n))
+                .map(|&(i, j)| (i, j, n)));
+        }
+
+        links
+    }
+
+    // What goes out when one of two candidates has to be true
+    fn either(&self, sudoku: &Sudoku, a: Candidate, b: Candidate) -> Vec<Candidate> {
+        let (a_cell, b_cell) = ((a.0, a.1), (b.0, b.1));
+
+        if a_cell == b_cell {
+            return self.get(a.0, a.1).iter()
+                .filter(|&&n| n != a.2 && n != b.2)
+                .map(|&n| (a.0, a.1, n))
+                .collect();
+        }
+
+        if a.2 == b.2 {
+            return self.seen_by_all(sudoku, &[a_cell, b_cell], a.2);
+        }
+
+        if !sees(sudoku, a_cell, b_cell) {
+            return Vec::new();
+        }
+
+        [(a.0, a.1, b.2), (b.0, b.1, a.2)].into_iter()
+            .filter(|&(row, col, n)| self.get(row, col).contains(&n))
+            .collect()
+    }
+
+    // Follows chains from every candidate, starting with a strong link and
+    // taking strong and weak ones in turns, shortest first. When it ends with
+    // a strong link one of its ends is true. When the last one also sees the
+    // first it's a loop, and every weak link in it has a true end.
+    pub(super) fn chain(&mut self, sudoku: &Sudoku, kind: Chain) -> Option<Deduction> {
+        let starts: Vec<Candidate> = self.empty_cells(sudoku).into_iter()
+            .flat_map(|(row, col)| {
+                let mut numbers: Vec<u8> = self.get(row, col).iter().copied().collect();
+                numbers.sort_unstable();
+                numbers.into_iter().map(move |n| (row, col, n))
+            })
+            .collect();
+
+        let strong: HashMap<Candidate, Vec<Candidate>> = starts.iter()
+            .map(|&candidate| (candidate, self.strong_links(sudoku, kind, candidate)))
+            .collect();
+        let weak: HashMap<Candidate, Vec<Candidate>> = starts.iter()
+            .map(|&candidate| (candidate, self.weak_links(sudoku, kind, candidate)))
+            .collect();
+
+        for &start in &starts {
+            // (candidate, got there by a strong link) and where it came from
+            let mut parents: HashMap<(Candidate, bool), (Candidate, bool)> = HashMap::new();
+            let mut queue = VecDeque::from([((start, false), 1)]);
+
+            while let Some(((node, by_strong), length)) = queue.pop_front() {
+                if length >= LONGEST {
+                    continue;
+                }
+
+                let next = match by_strong {
+                    false => &strong[&node],
+                    true => &weak[&node]
+                };
+
+                for &other in next {
+                    let state = (other, !by_strong);
+
+                    if other == start || parents.contains_key(&state) {
+                        continue;
+                    }
+
+                    parents.insert(state, (node, by_strong));
+
+                    let path = trace(&parents, state, start);
+                    if !is_simple(&path) {
+                        continue;
+                    }
+
+                    if !by_strong && path.len() >= 4 {
+                        let closes = weak[&other].contains(&start);
+
+                        if let Some(deduction) = self.conclude(sudoku, kind, path, closes) {
+                            return Some(deduction);
+                        }
+                    }
+
+                    queue.push_back((state, length + 1));
+                }
+            }
+        }
+
+        None
+    }
+
+    // The eliminations of a chain ending with a strong link, if any
+    fn conclude(&mut self, sudoku: &Sudoku, kind: Chain, path: Vec<Candidate>, closes: bool) -> Option<Deduction> {
+        let (first, last) = (path[0], path[path.len() - 1]);
+        let mut technique = match kind {
+            Chain::X => Technique::XChain,
+            Chain::XY => Technique::XYChain,
+            Chain::Alternating => Technique::Aic
+        };
+
+        let mut targets = self.either(sudoku, first, last);
+
+        if targets.is_empty() && closes && kind == Chain::Alternating {
+            technique = Technique::NiceLoop;
+
+            let mut links: Vec<_> = path.windows(2).skip(1).step_by(2).map(|pair| (pair[0], pair[1])).collect();
+            links.push((last, first));
+
+            for (a, b) in links {
+                targets.extend(self.either(sudoku, a, b).into_iter().filter(|target| !path.contains(target)));
+            }
+        }
+
+        let eliminations = self.eliminate(targets);
+
+        if eliminations.is_empty() {
+            return None;
+        }
+
+        let mut cells: Vec<(usize, usize)> = path.iter().map(|&(row, col, _)| (row, col)).collect();
+        cells.dedup();
+        let mut digits: Vec<u8> = path.iter().map(|&(_, _, n)| n).collect();
+        digits.sort_unstable();
+        digits.dedup();
+
+        Some(Deduction { technique, houses: Vec::new(), cells, digits, eliminations, chain: path })
+    }
+}
+
+// The path from the start to the state, following the parents back
+fn trace(
+    parents: &HashMap<(Candidate, bool), (Candidate, bool)>,
+    mut state: (Candidate, bool),
+    start: Candidate
+) -> Vec<Candidate> {
+    let mut path = vec![state.0];
+
+    while state.0 != start || state.1 {
+        state = parents[&state];
+        path.push(state.0);
+    }
+
+    path.reverse();
+    path
+}
+
+fn is_simple(path: &[Candidate]) -> bool {
+    path.iter().enumerate().all(|(i, candidate)| !path[..i].contains(candidate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xy_wing() {
+        let sudoku = Sudoku::new(9);
+        let wing = [((0, 0), &[1, 2][..]), ((0, 4), &[1, 3][..]), ((4, 0), &[2, 3][..])];
+        let mut candidates = Candidates::with_cells(&sudoku, &wing);
+
+        let deduction = candidates.xy_wing(&sudoku).unwrap();
+        assert_eq!(deduction.technique, Technique::XYWing);
+        assert_eq!(deduction.cells, vec![(0, 4), (0, 0), (4, 0)]);
+        assert_eq!(deduction.eliminations, vec![(4, 4, 3)]);
+        assert_eq!(
+            deduction.chain,
+            vec![(0, 4, 3), (0, 4, 1), (0, 0, 1), (0, 0, 2), (4, 0, 2), (4, 0, 3)]
+        );
+
+        // the same as a chain
+        let mut candidates = Candidates::with_cells(&sudoku, &wing);
+        let deduction = candidates.chain(&sudoku, Chain::Alternating).unwrap();
+        assert_eq!(deduction.technique, Technique::Aic);
+        assert_eq!(deduction.eliminations, vec![(4, 4, 3)]);
+        assert_eq!(deduction.chain.len(), 6);
+
+        let mut candidates = Candidates::with_cells(&sudoku, &wing);
+        let deduction = candidates.chain(&sudoku, Chain::XY).unwrap();
+        assert_eq!(deduction.technique, Technique::XYChain);
+        assert_eq!(deduction.eliminations, vec![(4, 4, 3)]);
+    }
+
+    #[test]
+    fn xyz_wing() {
+        let sudoku = Sudoku::new(9);
+        let mut candidates = Candidates::with_cells(&sudoku, &[
+            ((0, 0), &[1, 2, 3]),
+            ((0, 6), &[1, 3]),
+            ((2, 1), &[2, 3])
+        ]);
+
+        // 3 is out of the rest of the first row of the top left box
+        let deduction = candidates.xyz_wing(&sudoku).unwrap();
+        assert_eq!(deduction.technique, Technique::XYZWing);
+        assert_eq!(deduction.digits, vec![1, 2, 3]);
+        assert_eq!(deduction.eliminations, vec![(0, 1, 3), (0, 2, 3)]);
+    }
+
+    #[test]
+    fn w_wing() {
+        // 1 of the last row is in the first or sixth column, each seeing one
+        // of the cells with 1 and 2
+        let sudoku = Sudoku::new(9);
+        let mut candidates = Candidates::with_cells(&sudoku, &[((0, 0), &[1, 2]), ((4, 5), &[1, 2])]);
+        let targets: Vec<_> = (1..9).filter(|&col| col != 5).map(|col| (8, col, 1)).collect();
+        candidates.eliminate(targets);
+
+        let deduction = candidates.w_wing(&sudoku).unwrap();
+        assert_eq!(deduction.technique, Technique::WWing);
+        assert_eq!(deduction.eliminations, vec![(0, 5, 2), (4, 0, 2)]);
+        assert_eq!(
+            deduction.chain,
+            vec![(0, 0, 2), (0, 0, 1), (8, 0, 1), (8, 5, 1), (4, 5, 1), (4, 5, 2)]
+        );
+    }
+
+    #[test]
+    fn x_chain() {
+        // 1 only has two places in the first and sixth rows
+        let sudoku = Sudoku::new(9);
+        let mut candidates = Candidates::new(&sudoku);
+        let targets: Vec<_> = (0..9)
+            .flat_map(|col| [(0, col, 1), (5, col, 1)])
+            .filter(|&(row, col, _)| ![(0, 1), (0, 6), (5, 1), (5, 7)].contains(&(row, col)))
+            .collect();
+        candidates.eliminate(targets);
+
+        let deduction = candidates.chain(&sudoku, Chain::X).unwrap();
+        assert_eq!(deduction.technique, Technique::XChain);
+        assert_eq!(deduction.chain, vec![(0, 6, 1), (0, 1, 1), (5, 1, 1), (5, 7, 1)]);
+        assert_eq!(deduction.eliminations, vec![(1, 7, 1), (2, 7, 1), (3, 6, 1), (4, 6, 1)]);
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::hash_set::HashSet;
+
+use crate::sudoku::*;
+
+// The same number can't be a chess move apart, over the whole board
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chess {
+    AntiKnight,
+    AntiKing
+}
+
+impl Chess {
+    pub fn from_name(name: &str) -> Option<Chess> {
+        match name {
+            "antiknight" => Some(Chess::AntiKnight),
+            "antiking" => Some(Chess::AntiKing),
+            _ => None
+        }
+    }
+
+    fn moves(self) -> &'static [(isize, isize)] {
+        match self {
+            Chess::AntiKnight => &[(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)],
+            Chess::AntiKing => &[(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)]
+        }
+    }
+
+    // The cells a move away from this one
+    fn targets(self, size: usize, (row, col): (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+        self.moves().iter()
+            .map(move |&(d_row, d_col)| (row.wrapping_add_signed(d_row), col.wrapping_add_signed(d_col)))
+            .filter(move |&(i, j)| i < size && j < size)
+    }
+}
+
+impl Constraint for Chess {
+    fn check(&self, sudoku: &Sudoku, wrong_numbers: &mut HashSet<(usize, usize)>) {
+        let size = sudoku.size();
+
+        for (row, col, n) in sudoku.iterate() {
+            if n.value().is_none() {
+                continue;
+            }
+
+            for (i, j) in self.targets(size, (row, col)) {
+                if n.compare(sudoku.check_position(i, j)) {
+                    wrong_numbers.insert((row, col));
+                    wrong_numbers.insert((i, j));
+                }
+            }
+        }
+    }
+
+    fn prune(&self, sudoku: &Sudoku, row: usize, col: usize, candidates: &mut HashSet<u8>) {
+        for (i, j) in self.targets(sudoku.size(), (row, col)) {
+            if let Some(n) = sudoku.check_position(i, j).value() {
+                candidates.remove(&n);
+            }
+        }
+    }
+
+    fn encode(&self, sudoku: &Sudoku, cnf: &mut Cnf) {
+        for (row, col, _) in sudoku.iterate() {
+            for (i, j) in self.targets(sudoku.size(), (row, col)) {
+                if (i, j) < (row, col) || !sudoku.in_puzzle(i, j) {
+                    continue;
+                }
+
+                for n in 1..=sudoku.digits() as u8 {
+                    cnf.forbid((row, col, n), (i, j, n));
+                }
+            }
+        }
+    }
+
+    fn is_symmetric(&self) -> bool {
+        true
+    }
+
+    fn regenerate(&self) -> Regenerate {
+        Regenerate::KeepUnique
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Chess::AntiKnight => "antiknight",
+            Chess::AntiKing => "antiking"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chess_violations() {
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(Chess::AntiKnight);
+
+        sudoku.insert_number(0, 0, 5);
+        sudoku.insert_number(4, 4, 5);
+        assert!(sudoku.check_rules().is_empty());
+
+        sudoku.insert_number(5, 2, 5);
+        assert_eq!(sudoku.check_rules(), HashSet::from([(4, 4), (5, 2)]));
+
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(Chess::AntiKing);
+
+        sudoku.insert_number(2, 2, 7);
+        sudoku.insert_number(3, 4, 7);
+        assert!(sudoku.check_rules().is_empty());
+
+        sudoku.insert_number(3, 4, 1);
+        sudoku.insert_number(3, 3, 7);
+        assert_eq!(sudoku.check_rules(), HashSet::from([(2, 2), (3, 3)]));
+    }
+
+    #[test]
+    fn chess_candidates() {
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(Chess::AntiKnight);
+        sudoku.insert_given(0, 0, 1);
+        sudoku.insert_given(3, 1, 2);
+        sudoku.insert_given(8, 8, 3);
+
+        assert_eq!(pruned(&sudoku, 1, 2), HashSet::from_iter(3..=9));
+    }
+
+    #[test]
+    fn toggle_and_generate_unique() {
+        let mut sudoku = Sudoku::new(6);
+        assert!(sudoku.toggle_constraint(Chess::AntiKing));
+        assert!(sudoku.toggle_constraint(Chess::AntiKnight));
+        assert!(!sudoku.toggle_constraint(Chess::AntiKing));
+        assert_eq!(sudoku.constraints().len(), 1);
+
+        let puzzle = Sudoku::generate_unique(sudoku.blank_copy());
+
+        assert_eq!(puzzle.constraints()[0].name(), "antiknight");
+        assert!(puzzle.check_rules().is_empty());
+        assert!(puzzle.iterate().any(|(_, _, n)| *n == Number::Empty));
+        assert_eq!(puzzle.count_solutions(2), 1);
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::hash_map::HashMap;
+use std::collections::hash_set::HashSet;
+
+use crate::sudoku::*;
+use crate::sudoku::chains::sees;
+use crate::sudoku::logic::{Deduction, Technique};
+
+// (row, col)
+type Cell = (usize, usize);
+
+impl Candidates {
+    // The places of the number joined by houses where it only has two, in
+    // groups split in two colors so the places of each of those houses have
+    // different ones. In every group one color is all true and the other all
+    // false.
+    fn colors(&self, sudoku: &Sudoku, n: u8) -> Vec<[Vec<Cell>; 2]> {
+        let mut links: HashMap<Cell, Vec<Cell>> = HashMap::new();
+
+        for house in sudoku.houses() {
+            if let Some(&[a, b]) = self.places(sudoku, house, n).as_deref() {
+                links.entry(a).or_default().push(b);
+                links.entry(b).or_default().push(a);
+            }
+        }
+
+        let mut starts: Vec<Cell> = links.keys().copied().collect();
+        starts.sort_unstable();
+
+        let mut seen: HashSet<Cell> = HashSet::new();
+        let mut groups = Vec::new();
+
+        for start in starts {
+            if !seen.insert(start) {
+                continue;
+            }
+
+            let mut group = [Vec::new(), Vec::new()];
+            let mut stack = vec![(start, 0)];
+
+            while let Some((cell, color)) = stack.pop() {
+                group[color].push(cell);
+
+                for &other in &links[&cell] {
+                    if seen.insert(other) {
+                        stack.push((other, 1 - color));
+                    }
+                }
+            }
+
+            groups.push(group);
+        }
+
+        groups
+    }
+
+    // The other places of the number that see a cell of each of the colors
+    fn seeing_both(&self, sudoku: &Sudoku, n: u8, first: &[Cell], second: &[Cell]) -> Vec<(usize, usize, u8)> {
+        sudoku.iterate()
+            .map(|(row, col, _)| (row, col))
+            .filter(|&(row, col)| self.get(row, col).contains(&n))
+            .filter(|cell| !first.contains(cell) && !second.contains(cell))
+            .filter(|&cell| first.iter().any(|&a| sees(sudoku, cell, a)) && second.iter().any(|&b| sees(sudoku, cell, b)))
+            .map(|(row, col)| (row, col, n))
+            .collect()
+    }
+
+    fn colored(
+        &mut self,
+        technique: Technique,
+        n: u8,
+        cells: Vec<Cell>,
+        targets: Vec<(usize, usize, u8)>
+    ) -> Option<Deduction> {
+        let eliminations = self.eliminate(targets);
+
+        (!eliminations.is_empty()).then_some(Deduction {
+            technique,
+            houses: Vec::new(),
+            cells,
+            digits: vec![n],
+            eliminations,
+            chain: Vec::new()
+        })
+    }
+
+    // Within one group: a color with two cells that see each other is false,
+    // and places that see both colors can't have the number
+    pub(super) fn simple_coloring(&mut self, sudoku: &Sudoku) -> Option<Deduction> {
+        for n in 1..=sudoku.digits() as u8 {
+            for group in self.colors(sudoku, n) {
+                let cells: Vec<Cell> = group.concat();
+
+                for color in &group {
+                    let wrong = color.iter().any(|&a| color.iter().any(|&b| sees(sudoku, a, b)));
+
+                    if wrong {
+                        let targets = color.iter().map(|&(row, col)| (row, col, n)).collect();
+                        return self.colored(Technique::SimpleColoring, n, cells, targets);
+                    }
+                }
+
+                let targets = self.seeing_both(sudoku, n, &group[0], &group[1]);
+                if let Some(deduction) = self.colored(Technique::SimpleColoring, n, cells, targets) {
+                    return Some(deduction);
+                }
+            }
+        }
+
+        None
+    }
+
+    // Between two groups: when a color of one sees a color of the other they
+    // can't both be true, so one of the opposite colors is. A color that sees
+    // both colors of the other group is false.
+    pub(super) fn multi_coloring(&mut self, sudoku: &Sudoku) -> Option<Deduction> {
+        for n in 1..=sudoku.digits() as u8 {
+            let groups = self.colors(sudoku, n);
+
+            for (i, first) in groups.iter().enumerate() {
+                for (j, second) in groups.iter().enumerate() {
+                    if i == j {
+                        continue;
+                    }
+
+                    let cells: Vec<Cell> = first.concat().into_iter().chain(second.concat()).collect();
+                    let meet = |a: &[Cell], b: &[Cell]| a.iter().any(|&x| b.iter().any(|&y| sees(sudoku, x, y)));
+
+                    for a in 0..2 {
+                        if meet(&first[a], &second[0]) && meet(&first[a], &second[1]) {
+                            let targets = first[a].iter().map(|&(row, col)| (row, col, n)).collect();
+                            return self.colored(Technique::MultiColoring, n, cells, targets);
+                        }
+
+                        for b in 0..2 {
+                            if !meet(&first[a], &second[b]) {
+                                continue;
+                            }
+
+                            let targets = self.seeing_both(sudoku, n, &first[1 - a], &second[1 - b]);
+                            if let Some(deduction) = self.colored(Technique::MultiColoring, n, cells.clone(), targets) {
+                                return Some(deduction);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Candidates of an empty grid without the number in every cell of the
+    // houses but the ones given
+    fn with_places(sudoku: &Sudoku, n: u8, houses: &[(Vec<Cell>, Vec<Cell>)]) -> Candidates {
+        Candidates::keeping(sudoku, |cell, m| {
+            m != n || houses.iter().all(|(house, places)| !house.contains(&cell) || places.contains(&cell))
+        })
+    }
+
+    fn row(i: usize) -> Vec<Cell> {
+        (0..9).map(|j| (i, j)).collect()
+    }
+
+    fn col(j: usize) -> Vec<Cell> {
+        (0..9).map(|i| (i, j)).collect()
+    }
+
+    #[test]
+    fn simple_coloring() {
+        // 1 goes in (0, 0) and (6, 5), or in (0, 5) and (7, 3)
+        let sudoku = Sudoku::new(9);
+        let bottom_box = (6..9).flat_map(|i| (3..6).map(move |j| (i, j))).collect();
+        let mut candidates = with_places(&sudoku, 1, &[
+            (row(0), vec![(0, 0), (0, 5)]),
+            (col(5), vec![(0, 5), (6, 5)]),
+            (bottom_box, vec![(6, 5), (7, 3)])
+        ]);
+
+        let deduction = candidates.simple_coloring(&sudoku).unwrap();
+        assert_eq!(deduction.technique, Technique::SimpleColoring);
+        assert_eq!(deduction.cells, vec![(0, 0), (6, 5), (0, 5), (7, 3)]);
+        assert_eq!(deduction.eliminations, vec![(7, 0, 1)]);
+    }
+
+    #[test]
+    fn multi_coloring() {
+        // (0, 0) and (1, 1) can't both have 1, so it's in (0, 4) or (7, 1)
+        let sudoku = Sudoku::new(9);
+        let mut candidates = with_places(&sudoku, 1, &[
+            (row(0), vec![(0, 0), (0, 4)]),
+            (col(1), vec![(1, 1), (7, 1)])
+        ]);
+        assert_eq!(candidates.simple_coloring(&sudoku), None);
+
+        let deduction = candidates.multi_coloring(&sudoku).unwrap();
+        assert_eq!(deduction.technique, Technique::MultiColoring);
+        assert_eq!(deduction.eliminations, vec![(7, 4, 1)]);
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::hash_set::HashSet;
+
+use crate::sudoku::*;
+
+// Rules on top of the rows, columns and regions of the grid. The checker,
+// solver and renderer only go through this trait, so a variant can be added by
+// implementing it and adding it to the sudoku with `add_constraint`.
+pub trait Constraint: std::fmt::Debug + Send + Sync {
+    // Groups of cells that can't repeat numbers. The checker and the solver
+    // handle these like they handle rows, so rules that are only that don't
+    // need anything else.
+    fn houses(&self, _size: usize) -> Vec<Vec<(usize, usize)>> {
+        Vec::new()
+    }
+
+    // Adds the cells that break the rule (not counting repeats in the houses)
+    fn check(&self, _sudoku: &Sudoku, _wrong_numbers: &mut HashSet<(usize, usize)>) {}
+
+    // Removes the numbers that can't go in the empty cell because of this rule
+    fn prune(&self, _sudoku: &Sudoku, _row: usize, _col: usize, _candidates: &mut HashSet<u8>) {}
+
+    // Adds clauses for the rest of the rule to the ones of the houses, so a
+    // SAT solver can work on the puzzle (see dimacs.rs)
+    fn encode(&self, _sudoku: &Sudoku, _cnf: &mut Cnf) {}
+
+    // How to draw it
+    fn shapes(&self, _size: usize) -> Vec<Shape> {
+        Vec::new()
+    }
+
+    // What a new puzzle of the same kind does with it (see `generate_like`)
+    fn regenerate(&self) -> Regenerate {
+        Regenerate::Keep
+    }
+
+    // Whether the rule still holds with the numbers swapped for one another,
+    // as it's only about where the same numbers go
+    fn is_symmetric(&self) -> bool {
+        false
+    }
+
+    // Whether its clues are drawn outside the grid, which needs a margin
+    // around the board
+    fn outside(&self) -> bool {
+        false
+    }
+
+    // The word that starts its lines in puzzle files
+    fn name(&self) -> &'static str;
+}
+
+// Rules of the whole grid are kept in a new puzzle, while the clues of this
+// puzzle in particular are drawn again over the new solution if the generator
+// knows how
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Regenerate {
+    Keep,
+    // kept, but random givens easily have no solution, so the generator takes
+    // them out of a full solution instead
+    KeepUnique,
+    Thermometer,
+    Arrow,
+    Unsupported
+}
+
+// Which palette color a shape is drawn with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ink {
+    Grid,
+    Background,
+    Cage,
+    Diagonal,
+    House,
+    // numbers outside the grid, drawn like the givens
+    Given,
+    // thermometers, arrows and other marks on the cells
+    Clue
+}
+
+// Positions are (row, col) measured in cells, so (0.5, 0.5) is the center of
+// the top left cell, and sizes are in cells too
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    // fills a cell under everything else
+    Shade { cell: (usize, usize), ink: Ink },
+    Line { from: (f32, f32), to: (f32, f32), width: f32, ink: Ink },
+    Circle { center: (f32, f32), radius: f32, filled: bool, ink: Ink },
+    Text { center: (f32, f32), text: String, size: f32, ink: Ink },
+    // a dashed line just inside the border of a group of cells
+    Outline { cells: Vec<(usize, usize)>, ink: Ink }
+}
+
+// Both main diagonals need every number once, like in Sudoku-X
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagonals;
+
+impl Constraint for Diagonals {
+    fn houses(&self, size: usize) -> Vec<Vec<(usize, usize)>> {
+        vec![
+            (0..size).map(|i| (i, i)).collect(),
+            (0..size).map(|i| (i, size - 1 - i)).collect()
+        ]
+    }
+
+    fn shapes(&self, size: usize) -> Vec<Shape> {
+        let size = size as f32;
+
+        vec![
+            Shape::Line { from: (0.0, 0.0), to: (size, size), width: 0.04, ink: Ink::Diagonal },
+            Shape::Line { from: (size, 0.0), to: (0.0, size), width: 0.04, ink: Ink::Diagonal }
+        ]
+    }
+
+    fn is_symmetric(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "diagonals"
+    }
+}
+
+// The sum of the filled cells of a line that adds up to something, like an
+// arrow or a little killer, and how many are empty
+pub(super) fn filled(sudoku: &Sudoku, cells: &[(usize, usize)]) -> (usize, usize) {
+    cells.iter().fold((0, 0), |(sum, empty), &(row, col)| {
+        match sudoku.check_position(row, col).value() {
+            Some(n) => (sum + n as usize, empty),
+            None => (sum, empty + 1)
+        }
+    })
+}
+
+// The most an empty cell of the line can have for it to add up to `total`,
+// once the other empty cells have a 1 each
+pub(super) fn room(total: usize, sum: usize, empty: usize) -> usize {
+    total.saturating_sub(sum + empty - 1)
+}
+
+// What the constraints leave of every number for the cell, for their tests
+#[cfg(test)]
+pub(super) fn pruned(sudoku: &Sudoku, row: usize, col: usize) -> HashSet<u8> {
+    let mut candidates = HashSet::from_iter(1..=sudoku.digits() as u8);
+
+    for constraint in sudoku.constraints() {
+        constraint.prune(sudoku, row, col, &mut candidates);
+    }
+
+    candidates
+}
+
+// Reads a puzzle file and checks that the solver fills it following every
+// rule, returning the puzzle
+#[cfg(test)]
+pub(super) fn solves(path: &str) -> Result<Sudoku, anyhow::Error> {
+    let sudoku = Sudoku::from_file(std::path::Path::new(path))?;
+    let solution = sudoku.solve().ok_or(anyhow::anyhow!("no solution"))?;
+
+    assert!(solution.iterate().all(|(_, _, n)| *n != Number::Empty));
+    assert!(solution.check_rules().is_empty());
+
+    Ok(sudoku)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagonal_violations() {
+        let mut sudoku = Sudoku::new(9);
+        sudoku.insert_number(0, 0, 5);
+        sudoku.insert_number(8, 8, 5);
+        sudoku.insert_number(0, 8, 3);
+        sudoku.insert_number(4, 4, 3);
+        assert!(sudoku.check_rules().is_empty());
+
+        sudoku.add_constraint(Diagonals);
+        assert_eq!(
+            sudoku.check_rules(),
+            HashSet::from([(0, 0), (8, 8), (0, 8), (4, 4)])
+        );
+    }
+
+    #[test]
+    fn solve_diagonal() -> Result<(), anyhow::Error> {
+        let sudoku = solves("tests/diagonal")?;
+        assert_eq!(sudoku.constraints().len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn generate_follows_rules() {
+        let mut blank = Sudoku::new(9);
+        blank.add_constraint(Diagonals);
+
+        let sudoku = Sudoku::generate_from(blank);
+
+        assert_eq!(sudoku.constraints().len(), 1);
+        assert!(sudoku.check_rules().is_empty());
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::hash_map::HashMap;
+
+use crate::sudoku::*;
+
+// What can be wrong with the output of a SAT solver
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("The SAT solver found no solution")]
+    Unsatisfiable,
+    #[error("Can't read {text:?} in the model")]
+    InvalidModel { text: String },
+    #[error("The model doesn't have a single number for r{row}c{col} that fits")]
+    ModelCell { row: usize, col: usize }
+}
+
+// A puzzle as clauses for a SAT solver. There's a variable for every number of
+// every cell, true when the number is there, and the constraints add more of
+// their own for what they need to keep track of (see `automaton`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cnf {
+    size: usize,
+    digits: usize,
+    variables: usize,
+    clauses: Vec<Vec<i64>>
+}
+
+impl Cnf {
+    fn new(size: usize, digits: usize) -> Cnf {
+        Cnf { size, digits, variables: size * size * digits, clauses: Vec::new() }
+    }
+
+    // The variable of the number in the cell, from 1
+    pub fn var(&self, row: usize, col: usize, n: u8) -> i64 {
+        ((row * self.size + col) * self.digits + n as usize) as i64
+    }
+
+    // The cell and number of a variable, or None for the ones of the constraints
+    fn cell_of(&self, var: i64) -> Option<(usize, usize, u8)> {
+        let i = usize::try_from(var).ok()?.checked_sub(1)?;
+
+        (i < self.size * self.size * self.digits).then(|| {
+            let cell = i / self.digits;
+            (cell / self.size, cell % self.size, (i % self.digits) as u8 + 1)
+        })
+    }
+
+    fn fresh(&mut self) -> i64 {
+        self.variables += 1;
+        self.variables as i64
+    }
+
+    // At least one of the literals is true
+    pub fn clause(&mut self, literals: Vec<i64>) {
+        self.clauses.push(literals);
+    }
+
+    // The two numbers can't both be there
+    pub fn forbid(&mut self, (row, col, n): (usize, usize, u8), (other_row, other_col, m): (usize, usize, u8)) {
+        let clause = vec![-self.var(row, col, n), -self.var(other_row, other_col, m)];
+        self.clause(clause);
+    }
+
+    // Reads the numbers of the cells in order, going from state to state with
+    // `step`, where None means the numbers so far already break the rule, and
+    // the state after the last cell must be accepted. Every state after each
+    // cell gets a variable that has to be true when the numbers lead to it, so
+    // a rule like a sum only needs the states of its partial sums.
+    pub fn automaton<S: Clone + PartialEq>(
+        &mut self,
+        cells: &[(usize, usize)],
+        start: S,
+        step: impl Fn(&S, u8) -> Option<S>,
+        accept: impl Fn(&S) -> bool
+    ) {
+        let first = self.fresh();
+        self.clause(vec![first]);
+
+        let mut states = vec![(start, first)];
+
+        for &(row, col) in cells {
+            let mut next: Vec<(S, i64)> = Vec::new();
+
+            for (state, var) in &states {
+                for n in 1..=self.digits as u8 {
+                    let number = self.var(row, col, n);
+
+                    let Some(to) = step(state, n) else {
+                        self.clause(vec![-var, -number]);
+                        continue;
+                    };
+
+                    let to_var = match next.iter().find(|(other, _)| *other == to) {
+                        Some(&(_, to_var)) => to_var,
+                        None => {
+                            let to_var = self.fresh();
+                            next.push((to, to_var));
+                            to_var
+                        }
+                    };
+
+                    self.clause(vec![-var, -number, to_var]);
+                }
+            }
+
+            states = next;
+        }
+
+        for (state, var) in states {
+            if !accept(&state) {
+                self.clause(vec![-var]);
+            }
+        }
+    }
+
+    // In DIMACS CNF, what SAT solvers read
+    pub fn to_dimacs(&self) -> String {
+        let mut text = format!(
+            "c {0}x{0} board of numbers 1 to {1}, number n of cell (row, col) from 0 is (row * {0} + col) * {1} + n\n",
+            self.size,
+            self.digits
+        );
+        text += &format!("p cnf {} {}\n", self.variables, self.clauses.len());
+
+        for clause in &self.clauses {
+            for literal in clause {
+                text += &format!("{} ", literal);
+            }
+
+            text += "0\n";
+        }
+
+        text
+    }
+}
+
+impl Sudoku {
+    // Every cell of the puzzle has exactly one number, houses don't repeat
+    // them and the full ones have every number, the numbers on the board stay,
+    // and the constraints add the rest of their rules
+    pub fn to_cnf(&self) -> Cnf {
+        let digits = self.digits() as u8;
+        let mut cnf = Cnf::new(self.size(), self.digits());
+
+        for (row, col, number) in self.iterate() {
+            cnf.clause((1..=digits).map(|n| cnf.var(row, col, n)).collect());
+
+            for n in 1..=digits {
+                for m in n + 1..=digits {
+                    cnf.forbid((row, col, n), (row, col, m));
+                }
+            }
+
+            if let Some(n) = number.value() {
+                cnf.clause(vec![cnf.var(row, col, n)]);
+            }
+        }
+
+        for house in self.houses() {
+            for n in 1..=digits {
+                for (i, &(row, col)) in house.iter().enumerate() {
+                    for &(other_row, other_col) in &house[i + 1..] {
+                        cnf.forbid((row, col, n), (other_row, other_col, n));
+                    }
+                }
+
+                if house.len() == self.digits() {
+                    cnf.clause(house.iter().map(|&(row, col)| cnf.var(row, col, n)).collect());
+                }
+            }
+        }
+
+        for constraint in self.constraints() {
+            constraint.encode(self, &mut cnf);
+        }
+
+        cnf
+    }
+
+    // The board with the numbers of a model a SAT solver found for `to_cnf`,
+    // with "s" and "v" lines like in competitions, or the bare literals. The
+    // variables of the constraints are left out.
+    pub fn read_model(&self, text: &str) -> Result<Sudoku, Error> {
+        let cnf = Cnf::new(self.size(), self.digits());
+        let mut numbers: HashMap<(usize, usize), Vec<u8>> = HashMap::new();
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('c') {
+                continue;
+            }
+
+            if line.starts_with('s') || line.starts_with("SAT") || line.starts_with("UNSAT") {
+                if line.contains("UNSAT") {
+                    return Err(Error::Unsatisfiable);
+                }
+
+                continue;
+            }
+
+            for word in line.trim_start_matches('v').split_whitespace() {
+                let literal: i64 = word.parse()
+                    .map_err(|_| Error::InvalidModel { text: word.to_string() })?;
+
+                if let Some((row, col, n)) = cnf.cell_of(literal) {
+                    numbers.entry((row, col)).or_default().push(n);
+                }
+            }
+        }
+
+        let mut sudoku = self.clone();
+        let cells: Vec<(usize, usize, Number)> = self.iterate().map(|(row, col, n)| (row, col, *n)).collect();
+
+        for (row, col, number) in cells {
+            let wrong = Error::ModelCell { row: row + 1, col: col + 1 };
+
+            let n = match numbers.get(&(row, col)).map(Vec::as_slice) {
+                Some(&[n]) => n,
+                _ => return Err(wrong)
+            };
+
+            match number {
+                Number::Given(given) if given != n => return Err(wrong),
+                Number::Given(_) => {}
+                _ => sudoku.insert_number(row, col, n)
+            }
+        }
+
+        Ok(sudoku)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    // Whether the clauses hold with the numbers of a full board. The variables
+    // of the constraints follow from those, as long as nothing breaks.
+    fn satisfies(cnf: &Cnf, sudoku: &Sudoku) -> bool {
+        let mut values: HashMap<i64, bool> = HashMap::new();
+
+        for (row, col, number) in sudoku.iterate() {
+            for n in 1..=cnf.digits as u8 {
+                values.insert(cnf.var(row, col, n), number.value() == Some(n));
+            }
+        }
+
+        let value = |values: &HashMap<i64, bool>, literal: i64| {
+            values.get(&literal.abs()).map(|&value| value == (literal > 0))
+        };
+
+        loop {
+            let mut changed = false;
+
+            for clause in &cnf.clauses {
+                if clause.iter().any(|&literal| value(&values, literal) == Some(true)) {
+                    continue;
+                }
+
+                let open: Vec<i64> = clause.iter().copied().filter(|&literal| value(&values, literal).is_none()).collect();
+
+                match open[..] {
+                    [] => return false,
+                    [literal] => {
+                        values.insert(literal.abs(), literal > 0);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        cnf.clauses.iter().all(|clause| clause.iter().any(|&literal| value(&values, literal).unwrap_or(literal < 0)))
+    }
+
+    // The same puzzle with every cell filled in from another board
+    fn filled_with(sudoku: &Sudoku, other: &Sudoku) -> Sudoku {
+        let mut sudoku = sudoku.clone();
+
+        for (row, col, n) in other.iterate() {
+            sudoku.delete_position(row, col);
+            sudoku.insert_given(row, col, n.value().unwrap());
+        }
+
+        sudoku
+    }
+
+    #[test]
+    fn clauses_follow_the_rules() -> Result<(), anyhow::Error> {
+        let plain = Sudoku::from_file(Path::new("tests/example"))?.solve().unwrap();
+
+        for name in ["example", "killer", "thermo", "arrow", "kropki", "outside", "diagonal", "windoku", "jigsaw"] {
+            let sudoku = Sudoku::from_file(&Path::new("tests").join(name))?;
+            let solution = sudoku.solve().unwrap();
+            let cnf = sudoku.to_cnf();
+
+            assert!(satisfies(&cnf, &solution), "{}", name);
+
+            // another board breaks the clauses when it breaks some rule
+            let other = filled_with(&sudoku, &plain);
+            assert_eq!(satisfies(&other.to_cnf(), &other), other.check_rules().is_empty(), "{}", name);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn models() -> Result<(), anyhow::Error> {
+        let sudoku = Sudoku::from_file(Path::new("tests/samurai"))?;
+        let solution = sudoku.solve().unwrap();
+        let cnf = sudoku.to_cnf();
+        assert!(satisfies(&cnf, &solution));
+        assert!(cnf.to_dimacs().lines().nth(1).unwrap().starts_with(&format!("p cnf {} ", cnf.variables)));
+
+        let digits = cnf.digits as u8;
+        let var = |row, col, n| cnf.var(row, col, n);
+        let literals: Vec<String> = solution.iterate()
+            .flat_map(|(row, col, number)| (1..=digits).map(move |n| {
+                let var = var(row, col, n);
+                if number.value() == Some(n) { var } else { -var }
+            }))
+            .map(|literal| literal.to_string())
+            .collect();
+        let model = format!("c from a solver\ns SATISFIABLE\nv {}\nv {} 0\n", literals[..10].join(" "), literals[10..].join(" "));
+
+        let read = sudoku.read_model(&model)?;
+        assert_eq!(read, solution);
+        let missing: Vec<&str> = literals.iter()
+            .map(String::as_str)
+            .filter(|literal| *literal != cnf.var(0, 0, solution.check_position(0, 0).value().unwrap()).to_string())
+            .collect();
+        assert!(matches!(sudoku.read_model(&missing.join(" ")), Err(Error::ModelCell { row: 1, col: 1 })));
+        assert!(matches!(sudoku.read_model("s UNSATISFIABLE"), Err(Error::Unsatisfiable)));
+        assert!(matches!(sudoku.read_model("v 1 x 0"), Err(Error::InvalidModel { .. })));
+
+        Ok(())
+    }
+}
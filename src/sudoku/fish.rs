@@ -0,0 +1,160 @@
+use crate::sudoku::*;
+use crate::sudoku::logic::{combinations, Deduction, Technique};
+
+impl Candidates {
+    pub(super) fn fish(&mut self, sudoku: &Sudoku, size: usize) -> Option<Deduction> {
+        self.find_fish(sudoku, size, false)
+    }
+
+    pub(super) fn finned_fish(&mut self, sudoku: &Sudoku, size: usize) -> Option<Deduction> {
+        self.find_fish(sudoku, size, true)
+    }
+
+    // Looks at `size` base lines of a grid at a time (rows, then columns), and
+    // the cover lines going the other way that have their places for a number.
+    // With as many covers as bases the number has to be in those places, so
+    // it's out of the rest of the covers. With one or two covers more, the
+    // places in them are fins, and without them it would be a fish: either a
+    // fin has the number or the fish does, and the cells in the covers that see
+    // every fin can't have it either way.
+    fn find_fish(&mut self, sudoku: &Sudoku, size: usize, finned: bool) -> Option<Deduction> {
+        for grid in 0..sudoku.grids().len() {
+            let (rows, cols) = sudoku.lines(grid);
+
+            for (base, cover) in [(rows.clone(), cols.clone()), (cols, rows)] {
+                let cover_of = |(row, col): (usize, usize)| {
+                    sudoku.houses_of(row, col).iter().copied().find(|house| cover.contains(house))
+                };
+
+                for n in 1..=sudoku.digits() as u8 {
+                    let lines: Vec<(usize, Vec<(usize, usize)>)> = base.clone()
+                        .filter_map(|i| Some((i, self.places(sudoku, &sudoku.houses()[i], n)?)))
+                        .filter(|(_, places)| !places.is_empty())
+                        .collect();
+
+                    for bases in combinations(&lines, size) {
+                        let places: Vec<_> = bases.iter().flat_map(|(_, places)| places.iter().copied()).collect();
+                        let mut covers: Vec<usize> = places.iter().filter_map(|&cell| cover_of(cell)).collect();
+                        covers.sort_unstable();
+                        covers.dedup();
+
+                        let extra = covers.len().saturating_sub(size);
+                        if (!finned && extra != 0) || (finned && !(1..=2).contains(&extra)) {
+                            continue;
+                        }
+
+                        for chosen in combinations(&covers, size) {
+                            let fins: Vec<_> = places.iter().copied()
+                                .filter(|&cell| cover_of(cell).is_some_and(|house| !chosen.contains(&house)))
+                                .collect();
+
+                            // the region with every fin, or none needed without fins
+                            let region = match fins.first() {
+                                Some(&(row, col)) => {
+                                    let region = sudoku.houses_of(row, col).iter().copied().find(|&house| {
+                                        sudoku.is_region(house) && fins.iter().all(|fin| sudoku.houses()[house].contains(fin))
+                                    });
+
+                                    match region {
+                                        Some(region) => Some(&sudoku.houses()[region]),
+                                        None => continue
+                                    }
+                                }
+                                None => None
+                            };
+
+                            let targets: Vec<_> = chosen.iter()
+                                .flat_map(|&house| sudoku.houses()[house].iter().copied())
+                                .filter(|cell| !places.contains(cell) && region.is_none_or(|cells| cells.contains(cell)))
+                                .map(|(row, col)| (row, col, n))
+                                .collect();
+                            let eliminations = self.eliminate(targets);
+
+                            if eliminations.is_empty() {
+                                continue;
+                            }
+
+                            let technique = match finned {
+                                false => Technique::Fish { size },
+                                true => Technique::FinnedFish {
+                                    size,
+                                    sashimi: bases.iter().any(|(_, places)| {
+                                        places.iter().filter(|&&cell| cover_of(cell).is_some_and(|house| chosen.contains(&house))).count() <= 1
+                                    })
+                                }
+                            };
+
+                            return Some(Deduction {
+                                technique,
+                                houses: bases.iter().map(|&(i, _)| i).chain(chosen).collect(),
+                                cells: places,
+                                digits: vec![n],
+                                eliminations,
+                                chain: Vec::new()
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Candidates of an empty grid, with 1 only in these columns of the rows
+    fn with_ones(sudoku: &Sudoku, rows: &[(usize, &[usize])]) -> Candidates {
+        Candidates::keeping(sudoku, |(row, col), n| {
+            n != 1 || rows.iter().find(|&&(other, _)| other == row).is_none_or(|(_, cols)| cols.contains(&col))
+        })
+    }
+
+    #[test]
+    fn x_wing() {
+        let sudoku = Sudoku::new(9);
+        let mut candidates = with_ones(&sudoku, &[(0, &[2, 6]), (4, &[2, 6])]);
+
+        let deduction = candidates.fish(&sudoku, 2).unwrap();
+        assert_eq!(deduction.technique, Technique::Fish { size: 2 });
+        assert_eq!(deduction.houses, vec![0, 4, 11, 15]);
+        assert_eq!(deduction.cells, vec![(0, 2), (0, 6), (4, 2), (4, 6)]);
+        assert_eq!(deduction.eliminations.len(), 2 * 7);
+        assert!(!candidates.get(8, 6).contains(&1));
+        assert!(candidates.fish(&sudoku, 2).is_none());
+    }
+
+    #[test]
+    fn swordfish() {
+        let sudoku = Sudoku::new(9);
+        let mut candidates = with_ones(&sudoku, &[(1, &[0, 4]), (5, &[4, 8]), (7, &[0, 8])]);
+
+        assert!(candidates.fish(&sudoku, 2).is_none());
+
+        let deduction = candidates.fish(&sudoku, 3).unwrap();
+        assert_eq!(deduction.technique, Technique::Fish { size: 3 });
+        assert_eq!(deduction.eliminations.len(), 3 * 6);
+    }
+
+    #[test]
+    fn finned_x_wing() {
+        // the fin in r5c8 only lets the fish take 1 out of its box
+        let sudoku = Sudoku::new(9);
+        let mut candidates = with_ones(&sudoku, &[(0, &[2, 6]), (4, &[2, 6, 7])]);
+
+        assert!(candidates.fish(&sudoku, 2).is_none());
+
+        let deduction = candidates.finned_fish(&sudoku, 2).unwrap();
+        assert_eq!(deduction.technique, Technique::FinnedFish { size: 2, sashimi: false });
+        assert_eq!(deduction.eliminations, vec![(3, 6, 1), (5, 6, 1)]);
+
+        // without r5c7 it's sashimi
+        let mut candidates = with_ones(&sudoku, &[(0, &[2, 6]), (4, &[2, 7])]);
+        let deduction = candidates.finned_fish(&sudoku, 2).unwrap();
+        assert_eq!(deduction.technique, Technique::FinnedFish { size: 2, sashimi: true });
+        assert_eq!(deduction.eliminations, vec![(3, 6, 1), (5, 6, 1)]);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::sudoku::*;
+use crate::sudoku::logic::{Deduction, Technique};
+
+// (row, col, number)
+type Candidate = (usize, usize, u8);
+
+// What each branch of a forcing chain steps with, cheap enough to try every
+// number of a cell
+const BRANCH: Technique = Technique::BoxLine;
+
+impl Candidates {
+    // Whether the puzzle can't be finished: an empty cell or a number of a
+    // house with nowhere left to go, or a rule already broken
+    fn is_broken(&self, sudoku: &Sudoku) -> bool {
+        sudoku.iterate().any(|(row, col, n)| *n == Number::Empty && self.get(row, col).is_empty())
+            || sudoku.houses().iter().any(|house| {
+                (1..=sudoku.digits() as u8).any(|n| self.places(sudoku, house, n).is_some_and(|places| places.is_empty()))
+            })
+            || !sudoku.check_rules().is_empty()
+    }
+
+    // The board and candidates after putting the number in and stepping with
+    // the simpler techniques, or None when that breaks the puzzle
+    fn assume(&self, sudoku: &Sudoku, (row, col, n): Candidate) -> Option<(Sudoku, Candidates)> {
+        let mut sudoku = sudoku.clone();
+        let mut candidates = self.clone();
+
+        candidates.place(&mut sudoku, row, col, n);
+        candidates.propagate(&mut sudoku, BRANCH);
+
+        (!candidates.is_broken(&sudoku)).then_some((sudoku, candidates))
+    }
+
+    // One of the branches is true. The ones that break the puzzle are out,
+    // and so is anything every other branch takes out.
+    fn force(&mut self, sudoku: &Sudoku, branches: &[Candidate]) -> Vec<Candidate> {
+        let outcomes: Vec<_> = branches.iter().map(|&branch| self.assume(sudoku, branch)).collect();
+        let broken: Vec<Candidate> = branches.iter().zip(&outcomes)
+            .filter(|(_, outcome)| outcome.is_none())
+            .map(|(&branch, _)| branch)
+            .collect();
+
+        let alive: Vec<_> = outcomes.iter().flatten().collect();
+        if !broken.is_empty() || alive.is_empty() {
+            return broken;
+        }
+
+        let possible = |(after, candidates): &&(Sudoku, Candidates), (row, col, n): Candidate| {
+            after.check_position(row, col) == Number::Answer(n) || candidates.get(row, col).contains(&n)
+        };
+
+        sudoku.iterate()
+            .map(|(row, col, _)| (row, col))
+            .flat_map(|(row, col)| self.get(row, col).iter().map(move |&n| (row, col, n)))
+            .filter(|&candidate| alive.iter().all(|outcome| !possible(outcome, candidate)))
+            .collect()
+    }
+
+    // Tries every number of a cell with two or three
+    pub(super) fn cell_forcing_chain(&mut self, sudoku: &Sudoku) -> Option<Deduction> {
+        for (row, col, _) in sudoku.iterate().filter(|(_, _, n)| **n == Number::Empty) {
+            let mut numbers: Vec<u8> = self.get(row, col).iter().copied().collect();
+
+            if !(2..=3).contains(&numbers.len()) {
+                continue;
+            }
+
+            numbers.sort_unstable();
+            let branches: Vec<Candidate> = numbers.iter().map(|&n| (row, col, n)).collect();
+            let targets = self.force(sudoku, &branches);
+            let eliminations = self.eliminate(targets);
+
+            if !eliminations.is_empty() {
+                return Some(Deduction {
+                    technique: Technique::CellForcingChain,
+                    houses: Vec::new(),
+                    cells: vec![(row, col)],
+                    digits: numbers,
+                    eliminations,
+                    chain: Vec::new()
+                });
+            }
+        }
+
+        None
+    }
+
+    // Tries every place of a number with two or three in a house
+    pub(super) fn unit_forcing_chain(&mut self, sudoku: &Sudoku) -> Option<Deduction> {
+        for (i, house) in sudoku.houses().iter().enumerate() {
+            for n in 1..=sudoku.digits() as u8 {
+                let Some(places) = self.places(sudoku, house, n) else {
+                    continue;
+                };
+
+                if !(2..=3).contains(&places.len()) {
+                    continue;
+                }
+
+                let branches: Vec<Candidate> = places.iter().map(|&(row, col)| (row, col, n)).collect();
+                let targets = self.force(sudoku, &branches);
+                let eliminations = self.eliminate(targets);
+
+                if !eliminations.is_empty() {
+                    return Some(Deduction {
+                        technique: Technique::UnitForcingChain,
+                        houses: vec![i],
+                        cells: places,
+                        digits: vec![n],
+                        eliminations,
+                        chain: Vec::new()
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forcing_chains() -> Result<(), anyhow::Error> {
+        // the colorings and everything before them get stuck early on this one
+        let mut sudoku = Sudoku::from_file(std::path::Path::new("tests/escargot"))?;
+        let solution = sudoku.solve().ok_or(anyhow::anyhow!("no solution"))?;
+        let mut candidates = Candidates::new(&sudoku);
+        candidates.propagate(&mut sudoku, Technique::MultiColoring);
+
+        let is_right = |eliminations: &[Candidate]| eliminations.iter()
+            .all(|&(row, col, n)| solution.check_position(row, col).value() != Some(n));
+
+        let deduction = candidates.unit_forcing_chain(&sudoku).unwrap();
+        assert_eq!(deduction.technique, Technique::UnitForcingChain);
+        assert!(!deduction.eliminations.is_empty());
+        assert!(is_right(&deduction.eliminations));
+
+        let deduction = candidates.cell_forcing_chain(&sudoku).unwrap();
+        assert_eq!(deduction.technique, Technique::CellForcingChain);
+        assert!(is_right(&deduction.eliminations));
+
+        Ok(())
+    }
+}
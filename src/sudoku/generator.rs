@@ -1,20 +1,221 @@
+use std::collections::hash_set::HashSet;
+
 use crate::sudoku::*;
 use rand::Rng;
+use rand::seq::SliceRandom;
 
 impl Sudoku {
-   pub fn generate(size: usize) -> Sudoku {
+   // Another puzzle with the same rules, with as many thermometers and arrows
+   // as this one drawn again, or an error when it has clues the generator
+   // can't make
+   pub fn generate_like(&self) -> Result<Sudoku, Error> {
+       let kinds: Vec<Regenerate> = self.constraints().iter().map(|c| c.regenerate()).collect();
+       let count = |kind| kinds.iter().filter(|&&other| other == kind).count();
+
+       if let Some(c) = self.constraints().iter().find(|c| c.regenerate() == Regenerate::Unsupported) {
+           return Err(Error::CantRegenerate { name: c.name() });
+       }
+
+       let blank = self.blank_copy();
+
+       Ok(match (count(Regenerate::Thermometer), count(Regenerate::Arrow)) {
+           (0, 0) if count(Regenerate::KeepUnique) > 0 => Sudoku::generate_unique(blank),
+           (0, 0) => Sudoku::generate_from(blank),
+           (thermometers, arrows) => Sudoku::generate_with_lines(blank, thermometers, arrows)
+       })
+   }
+
+   // Fills a blank sudoku with random givens that follow its rules
+   pub fn generate_from(mut sudoku: Sudoku) -> Sudoku {
        let mut rng = rand::thread_rng();
-       let mut sudoku = Sudoku::new(size);
+       let size = sudoku.size();
 
        for _ in 0..size*size/2 {
            let i = rng.gen_range(0..size);
            let j = rng.gen_range(0..size);
-           let n = rng.gen_range(1..=size);
+           let n = rng.gen_range(1..=sudoku.digits());
+
+           if sudoku.check_position(i, j) != Number::Empty || !sudoku.in_puzzle(i, j) {
+               continue;
+           }
 
            sudoku.insert_given(i, j, n as u8);
+
+           if !sudoku.check_rules().is_empty() {
+               sudoku.delete_position(i, j);
+           }
+       }
+
+       sudoku
+   }
+
+   // A puzzle with thermometers and arrows drawn over a random solution of the
+   // blank sudoku, and about a third of that solution as givens
+   pub fn generate_with_lines(blank: Sudoku, thermometers: usize, arrows: usize) -> Sudoku {
+       let mut rng = rand::thread_rng();
+       let size = blank.size();
+
+       let Some(solution) = random_solution(&mut rng, &blank) else {
+           return Sudoku::generate_from(blank);
+       };
+       let value = |(row, col): (usize, usize)| solution.check_position(row, col).value().unwrap_or(0);
+
+       let mut sudoku = blank;
+       let mut used = HashSet::new();
+
+       for _ in 0..thermometers {
+           let path = (0..100)
+               .map(|_| random_path(&mut rng, size, &used, |path, next| {
+                   path.len() < 6 && value(next) > value(path[path.len() - 1])
+               }))
+               .find(|path| path.len() >= 3);
+
+           if let Some(path) = path {
+               used.extend(path.iter().copied());
+               sudoku.add_constraint(Thermometer::new(path));
+           }
+       }
+
+       for _ in 0..arrows {
+           let arrow = (0..100)
+               .map(|_| random_path(&mut rng, size, &used, |path, next| {
+                   let sum: usize = path[1..].iter().map(|&cell| value(cell) as usize).sum();
+                   sum + (value(next) as usize) <= value(path[0]) as usize
+               }))
+               .find(|path| {
+                   path.len() >= 3
+                       && path[1..].iter().map(|&cell| value(cell) as usize).sum::<usize>() == value(path[0]) as usize
+               });
+
+           if let Some(path) = arrow {
+               used.extend(path.iter().copied());
+               sudoku.add_constraint(Arrow::new(path[0], path[1..].to_vec()));
+           }
+       }
+
+       for (row, col, n) in solution.iterate() {
+           if let (Some(n), true) = (n.value(), rng.gen_ratio(1, 3)) {
+               sudoku.insert_given(row, col, n);
+           }
+       }
+
+       sudoku
+   }
+
+   // A puzzle with a single solution: starting from a random solution of the
+   // blank sudoku, takes out the givens in a random order while the rest still
+   // only have that solution
+   pub fn generate_unique(blank: Sudoku) -> Sudoku {
+       let mut rng = rand::thread_rng();
+
+       let Some(solution) = random_solution(&mut rng, &blank) else {
+           return Sudoku::generate_from(blank);
+       };
+
+       let mut sudoku = blank;
+       let mut givens = Vec::new();
+
+       for (row, col, n) in solution.iterate() {
+           if let Some(n) = n.value() {
+               sudoku.insert_given(row, col, n);
+               givens.push((row, col, n));
+           }
+       }
+
+       givens.shuffle(&mut rng);
+
+       for (row, col, n) in givens {
+           sudoku.delete_position(row, col);
+
+           if sudoku.count_solutions(2) != 1 {
+               sudoku.insert_given(row, col, n);
+           }
        }
 
        sudoku
    }
 }
 
+// A random solution of a blank sudoku. When its rules don't care which number
+// is which any first row can be completed like any other, so it starts from a
+// shuffled one; otherwise the search has to find a first row that works too.
+fn random_solution(rng: &mut impl Rng, blank: &Sudoku) -> Option<Sudoku> {
+   if blank.grids().len() != 1 {
+       return None;
+   }
+
+   let mut start = blank.clone();
+
+   if blank.constraints().iter().all(|constraint| constraint.is_symmetric()) {
+       let mut first_row: Vec<u8> = (1..=blank.size() as u8).collect();
+       first_row.shuffle(rng);
+
+       for (col, &n) in first_row.iter().enumerate() {
+           start.insert_given(0, col, n);
+       }
+   }
+
+   let mut solutions = Vec::new();
+   start.search(1, &mut |candidates| candidates.shuffle(rng), &mut solutions);
+
+   solutions.pop()
+}
+
+// A walk from a random cell through neighbouring cells (diagonals too) that
+// aren't `used`, as long as `extend` accepts the next cell
+fn random_path(
+   rng: &mut impl Rng,
+   size: usize,
+   used: &HashSet<(usize, usize)>,
+   extend: impl Fn(&[(usize, usize)], (usize, usize)) -> bool
+) -> Vec<(usize, usize)> {
+   let start = (rng.gen_range(0..size), rng.gen_range(0..size));
+
+   if used.contains(&start) {
+       return Vec::new();
+   }
+
+   let mut path = vec![start];
+
+   loop {
+       let (row, col) = path[path.len() - 1];
+       let neighbours: Vec<_> = (row.saturating_sub(1)..=(row + 1).min(size - 1))
+           .flat_map(|i| (col.saturating_sub(1)..=(col + 1).min(size - 1)).map(move |j| (i, j)))
+           .filter(|cell| !used.contains(cell) && !path.contains(cell) && extend(&path, *cell))
+           .collect();
+
+       match neighbours.choose(rng) {
+           Some(&next) => path.push(next),
+           None => return path
+       }
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn generate_lines() {
+       let sudoku = Sudoku::generate_with_lines(Sudoku::new(9), 3, 2);
+       let count = |name| sudoku.constraints().iter().filter(|c| c.name() == name).count();
+
+       assert!(count("thermo") <= 3);
+       assert!(count("arrow") <= 2);
+       assert!(count("thermo") + count("arrow") > 0);
+       assert!(sudoku.check_rules().is_empty());
+       assert!(sudoku.solve().is_some());
+   }
+
+   #[test]
+   fn solutions_of_any_rules() {
+       // most first rows can't go with this cage
+       let mut blank = Sudoku::new(4);
+       blank.add_constraint(Cage::new(3, vec![(0, 0), (0, 1)]));
+
+       for _ in 0..10 {
+           let solution = random_solution(&mut rand::thread_rng(), &blank).unwrap();
+           assert!(solution.check_rules().is_empty());
+       }
+   }
+}
@@ -0,0 +1,99 @@
+use crate::sudoku::*;
+
+// A group of cells that can't repeat numbers, on top of the rows, columns and
+// regions. It can overlap with any of them, like the four windows of Windoku.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtraHouse {
+    // (row, col)
+    pub cells: Vec<(usize, usize)>
+}
+
+impl ExtraHouse {
+    pub fn new(cells: Vec<(usize, usize)>) -> ExtraHouse {
+        ExtraHouse { cells }
+    }
+
+    // The windows of Windoku: the boxes that sit between the usual ones, one
+    // cell away from the borders. Only for square boxes.
+    pub fn windows(size: usize) -> Vec<ExtraHouse> {
+        let side = (1..=size).find(|s| s * s >= size).unwrap_or(1);
+
+        if side * side != size {
+            return Vec::new();
+        }
+
+        let mut windows = Vec::new();
+
+        for top in (1..size).step_by(side + 1).take(side - 1) {
+            for left in (1..size).step_by(side + 1).take(side - 1) {
+                let cells = (top..top + side)
+                    .flat_map(|row| (left..left + side).map(move |col| (row, col)))
+                    .collect();
+
+                windows.push(ExtraHouse::new(cells));
+            }
+        }
+
+        windows
+    }
+}
+
+impl Constraint for ExtraHouse {
+    fn houses(&self, _size: usize) -> Vec<Vec<(usize, usize)>> {
+        vec![self.cells.clone()]
+    }
+
+    fn shapes(&self, _size: usize) -> Vec<Shape> {
+        self.cells.iter()
+            .map(|&cell| Shape::Shade { cell, ink: Ink::House })
+            .collect()
+    }
+
+    fn is_symmetric(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "house"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_set::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn windows() {
+        let windows = ExtraHouse::windows(9);
+
+        assert_eq!(windows.len(), 4);
+        assert_eq!(windows[0].cells[0], (1, 1));
+        assert_eq!(windows[3].cells[8], (7, 7));
+        assert!(windows.iter().any(|w| w.cells.contains(&(3, 5))));
+        assert!(!windows.iter().any(|w| w.cells.contains(&(4, 4))));
+        assert!(ExtraHouse::windows(6).is_empty());
+    }
+
+    #[test]
+    fn extra_house_violations() {
+        let mut sudoku = Sudoku::new(9);
+        for window in ExtraHouse::windows(9) {
+            sudoku.add_constraint(window);
+        }
+
+        // same window, different row, column and box
+        sudoku.insert_number(2, 3, 4);
+        sudoku.insert_number(3, 1, 4);
+        assert_eq!(sudoku.check_rules(), HashSet::from([(2, 3), (3, 1)]));
+    }
+
+    #[test]
+    fn solve_windoku() -> Result<(), anyhow::Error> {
+        let sudoku = solves("tests/windoku")?;
+        assert_eq!(sudoku.constraints().len(), 4);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::hash_set::HashSet;
+
+use crate::sudoku::*;
+
+// A set of cells whose numbers add up to `sum`, without repeating any number
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cage {
+    pub sum: u32,
+    // (row, col)
+    pub cells: Vec<(usize, usize)>
+}
+
+impl Cage {
+    pub fn new(sum: u32, cells: Vec<(usize, usize)>) -> Cage {
+        Cage { sum, cells }
+    }
+
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        self.cells.contains(&(row, col))
+    }
+
+    // The cell where the sum is written, the top left one
+    pub fn label_cell(&self) -> Option<(usize, usize)> {
+        self.cells.iter().min().copied()
+    }
+}
+
+impl Constraint for Cage {
+    fn houses(&self, _size: usize) -> Vec<Vec<(usize, usize)>> {
+        vec![self.cells.clone()]
+    }
+
+    // Repeats are checked with the houses, this only checks the sum
+    fn check(&self, sudoku: &Sudoku, wrong_numbers: &mut HashSet<(usize, usize)>) {
+        let mut sum = 0;
+        let mut full = true;
+
+        for &(row, col) in &self.cells {
+            match sudoku.check_position(row, col) {
+                Number::Empty => full = false,
+                Number::Given(n) | Number::Answer(n) => sum += n as u32
+            }
+        }
+
+        if sum > self.sum || (full && sum != self.sum) {
+            wrong_numbers.extend(self.cells.iter().copied());
+        }
+    }
+
+    // Keeps the numbers that still let the cage add up to its sum
+    fn prune(&self, sudoku: &Sudoku, row: usize, col: usize, candidates: &mut HashSet<u8>) {
+        if !self.contains(row, col) {
+            return;
+        }
+
+        let mut used = HashSet::new();
+        let mut empty = 0;
+
+        for &(i, j) in &self.cells {
+            match sudoku.check_position(i, j) {
+                Number::Empty => empty += 1,
+                Number::Given(n) | Number::Answer(n) => { used.insert(n); }
+            }
+        }
+
+        let filled: u32 = used.iter().map(|n| *n as u32).sum();
+        let Some(remaining) = self.sum.checked_sub(filled) else {
+            candidates.clear();
+            return;
+        };
+        let available: Vec<u8> = (1..=sudoku.digits() as u8)
+            .filter(|n| !used.contains(n))
+            .collect();
+
+        candidates.retain(|&n| {
+            let rest: Vec<u8> = available.iter().copied().filter(|&m| m != n).collect();
+
+            (n as u32) <= remaining && can_sum(remaining - n as u32, empty - 1, &rest)
+        });
+    }
+
+    // The sums so far, which can't go over
+    fn encode(&self, _sudoku: &Sudoku, cnf: &mut Cnf) {
+        let sum = self.sum;
+
+        cnf.automaton(
+            &self.cells,
+            0,
+            |&partial: &u32, n| Some(partial + n as u32).filter(|&partial| partial <= sum),
+            |&partial| partial == sum
+        );
+    }
+
+    fn shapes(&self, _size: usize) -> Vec<Shape> {
+        let mut shapes = vec![Shape::Outline { cells: self.cells.clone(), ink: Ink::Cage }];
+
+        if let Some((row, col)) = self.label_cell() {
+            shapes.push(Shape::Text {
+                center: (row as f32 + 0.2, col as f32 + 0.2),
+                text: self.sum.to_string(),
+                size: 0.2,
+                ink: Ink::Cage
+            });
+        }
+
+        shapes
+    }
+
+    fn regenerate(&self) -> Regenerate {
+        Regenerate::Unsupported
+    }
+
+    fn name(&self) -> &'static str {
+        "cage"
+    }
+}
+
+// Whether `count` different numbers from `numbers` (sorted) can add up to `target`
+fn can_sum(target: u32, count: usize, numbers: &[u8]) -> bool {
+    if count == 0 {
+        return target == 0;
+    }
+
+    numbers.iter().enumerate().any(|(i, &n)| {
+        n as u32 <= target && can_sum(target - n as u32, count - 1, &numbers[i + 1..])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cage_violations() {
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(Cage::new(10, vec![(0, 0), (0, 1), (1, 0)]));
+        sudoku.add_constraint(Cage::new(3, vec![(8, 7), (8, 8)]));
+
+        sudoku.insert_number(0, 0, 2);
+        sudoku.insert_number(0, 1, 3);
+        assert!(sudoku.check_rules().is_empty());
+
+        // full, but adds up to 9
+        sudoku.insert_number(1, 0, 4);
+        assert_eq!(sudoku.check_rules(), HashSet::from([(0, 0), (0, 1), (1, 0)]));
+
+        // repeated inside the cage, but in a different row, column and box
+        sudoku.insert_number(1, 0, 5);
+        sudoku.add_constraint(Cage::new(12, vec![(2, 2), (2, 3), (3, 3)]));
+        sudoku.insert_number(2, 2, 6);
+        sudoku.insert_number(3, 3, 6);
+        assert_eq!(sudoku.check_rules(), HashSet::from([(2, 2), (3, 3)]));
+
+        // already over the sum
+        sudoku.insert_number(2, 2, 1);
+        sudoku.insert_number(8, 8, 4);
+        assert_eq!(sudoku.check_rules(), HashSet::from([(8, 7), (8, 8)]));
+    }
+
+    #[test]
+    fn cage_candidates() {
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(Cage::new(3, vec![(0, 0), (0, 1)]));
+        sudoku.add_constraint(Cage::new(23, vec![(5, 5), (5, 6), (5, 7)]));
+        sudoku.add_constraint(Cage::new(15, vec![(8, 0), (8, 1), (8, 2)]));
+        sudoku.insert_given(8, 0, 9);
+
+        assert_eq!(pruned(&sudoku, 0, 0), HashSet::from([1, 2]));
+        assert_eq!(pruned(&sudoku, 5, 6), HashSet::from([6, 8, 9]));
+        assert_eq!(pruned(&sudoku, 8, 1), HashSet::from([1, 2, 4, 5]));
+        assert_eq!(pruned(&sudoku, 4, 4), HashSet::from_iter(1..=9));
+    }
+
+    #[test]
+    fn solve_killer() -> Result<(), anyhow::Error> {
+        let sudoku = solves("tests/killer")?;
+        assert_eq!(sudoku.constraints().len(), 27);
+
+        Ok(())
+    }
+}
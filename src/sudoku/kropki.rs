@@ -0,0 +1,262 @@
+use std::collections::hash_map::HashMap;
+use std::collections::hash_set::HashSet;
+
+use crate::sudoku::*;
+
+// Marks on the edge between two orthogonally adjacent cells
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Marker {
+    // consecutive numbers
+    White,
+    // one is double the other
+    Black,
+    // they add up to 10
+    X,
+    // they add up to 5
+    V
+}
+
+impl Marker {
+    pub fn from_name(name: &str) -> Option<Marker> {
+        match name {
+            "white" => Some(Marker::White),
+            "black" => Some(Marker::Black),
+            "x" => Some(Marker::X),
+            "v" => Some(Marker::V),
+            _ => None
+        }
+    }
+
+    pub fn allows(self, a: u8, b: u8) -> bool {
+        match self {
+            Marker::White => a.abs_diff(b) == 1,
+            Marker::Black => a == 2 * b || b == 2 * a,
+            Marker::X => a + b == 10,
+            Marker::V => a + b == 5
+        }
+    }
+}
+
+type Edge = ((usize, usize), (usize, usize));
+
+// All the dots and X/V marks of a puzzle. With a negative marker every pair
+// of adjacent cells without marks can't follow its rule, which is what "all
+// dots given" means.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Markers {
+    edges: HashMap<Edge, Marker>,
+    negative: HashSet<Marker>
+}
+
+impl Markers {
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty() && self.negative.is_empty()
+    }
+
+    // Only between orthogonally adjacent cells
+    pub fn add(&mut self, a: (usize, usize), b: (usize, usize), marker: Marker) -> bool {
+        if a.0.abs_diff(b.0) + a.1.abs_diff(b.1) != 1 {
+            return false;
+        }
+
+        self.edges.insert((a.min(b), a.max(b)), marker);
+
+        true
+    }
+
+    pub fn add_negative(&mut self, marker: Marker) {
+        self.negative.insert(marker);
+    }
+
+    // The cells next to this one, with the marker between them if any
+    fn neighbours(&self, size: usize, (row, col): (usize, usize)) -> Vec<((usize, usize), Option<Marker>)> {
+        [(row.wrapping_sub(1), col), (row + 1, col), (row, col.wrapping_sub(1)), (row, col + 1)]
+            .into_iter()
+            .filter(|&(i, j)| i < size && j < size)
+            .map(|other| {
+                let edge = ((row, col).min(other), (row, col).max(other));
+                (other, self.edges.get(&edge).copied())
+            })
+            .collect()
+    }
+
+    // Whether two numbers can be on each side of an edge
+    fn allows(&self, marker: Option<Marker>, a: u8, b: u8) -> bool {
+        match marker {
+            Some(marker) => marker.allows(a, b),
+            None => !self.negative.iter().any(|negative| negative.allows(a, b))
+        }
+    }
+}
+
+impl Constraint for Markers {
+    fn check(&self, sudoku: &Sudoku, wrong_numbers: &mut HashSet<(usize, usize)>) {
+        let size = sudoku.size();
+
+        for (row, col, n) in sudoku.iterate() {
+            let Some(a) = n.value() else {
+                continue;
+            };
+
+            for (other, marker) in self.neighbours(size, (row, col)) {
+                let Some(b) = sudoku.check_position(other.0, other.1).value() else {
+                    continue;
+                };
+
+                if !self.allows(marker, a, b) {
+                    wrong_numbers.insert((row, col));
+                    wrong_numbers.insert(other);
+                }
+            }
+        }
+    }
+
+    fn prune(&self, sudoku: &Sudoku, row: usize, col: usize, candidates: &mut HashSet<u8>) {
+        let size = sudoku.size();
+
+        for (other, marker) in self.neighbours(size, (row, col)) {
+            match sudoku.check_position(other.0, other.1).value() {
+                Some(b) => candidates.retain(|&a| self.allows(marker, a, b)),
+                // a marked edge still needs some number on the other side
+                None => if let Some(marker) = marker {
+                    candidates.retain(|&a| (1..=sudoku.digits() as u8).any(|b| b != a && marker.allows(a, b)));
+                }
+            }
+        }
+    }
+
+    // The pairs of numbers each edge can't have, going over every edge once
+    fn encode(&self, sudoku: &Sudoku, cnf: &mut Cnf) {
+        let digits = sudoku.digits() as u8;
+
+        for (row, col, _) in sudoku.iterate() {
+            for (other, marker) in self.neighbours(sudoku.size(), (row, col)) {
+                if other < (row, col) || !sudoku.in_puzzle(other.0, other.1) {
+                    continue;
+                }
+
+                for a in 1..=digits {
+                    for b in (1..=digits).filter(|&b| !self.allows(marker, a, b)) {
+                        cnf.forbid((row, col, a), (other.0, other.1, b));
+                    }
+                }
+            }
+        }
+    }
+
+    fn shapes(&self, _size: usize) -> Vec<Shape> {
+        let mut shapes = Vec::new();
+
+        for (&(a, b), &marker) in &self.edges {
+            let center = (
+                (a.0 + b.0) as f32 / 2.0 + 0.5,
+                (a.1 + b.1) as f32 / 2.0 + 0.5
+            );
+            let background = Shape::Circle { center, radius: 0.13, filled: true, ink: Ink::Background };
+
+            match marker {
+                Marker::White => {
+                    shapes.push(background);
+                    shapes.push(Shape::Circle { center, radius: 0.13, filled: false, ink: Ink::Grid });
+                }
+                Marker::Black => {
+                    shapes.push(Shape::Circle { center, radius: 0.13, filled: true, ink: Ink::Grid });
+                }
+                Marker::X | Marker::V => {
+                    let text = if marker == Marker::X { "X" } else { "V" };
+
+                    shapes.push(background);
+                    shapes.push(Shape::Text { center, text: text.to_string(), size: 0.3, ink: Ink::Grid });
+                }
+            }
+        }
+
+        shapes
+    }
+
+    fn regenerate(&self) -> Regenerate {
+        Regenerate::Unsupported
+    }
+
+    fn name(&self) -> &'static str {
+        "kropki"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker_violations() {
+        let mut markers = Markers::default();
+        assert!(markers.add((0, 0), (0, 1), Marker::White));
+        assert!(markers.add((1, 0), (0, 0), Marker::Black));
+        assert!(markers.add((4, 4), (4, 5), Marker::X));
+        assert!(!markers.add((5, 5), (6, 6), Marker::V));
+
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(markers);
+
+        sudoku.insert_number(0, 0, 4);
+        sudoku.insert_number(0, 1, 5);
+        sudoku.insert_number(1, 0, 2);
+        sudoku.insert_number(4, 4, 3);
+        sudoku.insert_number(4, 5, 7);
+        assert!(sudoku.check_rules().is_empty());
+
+        sudoku.insert_number(0, 1, 6);
+        sudoku.insert_number(4, 5, 6);
+        assert_eq!(sudoku.check_rules(), HashSet::from([(0, 0), (0, 1), (4, 4), (4, 5)]));
+    }
+
+    #[test]
+    fn negative_markers() {
+        let mut markers = Markers::default();
+        markers.add((0, 0), (0, 1), Marker::Black);
+        markers.add_negative(Marker::White);
+        markers.add_negative(Marker::V);
+
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(markers);
+
+        // 1 and 2 have a black dot, so they can be consecutive
+        sudoku.insert_number(0, 0, 1);
+        sudoku.insert_number(0, 1, 2);
+        sudoku.insert_number(1, 1, 9);
+        assert!(sudoku.check_rules().is_empty());
+
+        // no marks between them
+        sudoku.insert_number(1, 1, 3);
+        assert_eq!(sudoku.check_rules(), HashSet::from([(0, 1), (1, 1)]));
+
+        sudoku.insert_number(1, 1, 9);
+        sudoku.insert_number(1, 0, 4);
+        assert_eq!(sudoku.check_rules(), HashSet::from([(0, 0), (1, 0)]));
+    }
+
+    #[test]
+    fn marker_candidates() {
+        let mut markers = Markers::default();
+        markers.add((0, 0), (0, 1), Marker::Black);
+        markers.add((8, 7), (8, 8), Marker::X);
+        markers.add_negative(Marker::White);
+
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(markers);
+        sudoku.insert_given(8, 8, 3);
+        sudoku.insert_given(5, 5, 5);
+
+        assert_eq!(pruned(&sudoku, 0, 0), HashSet::from([1, 2, 3, 4, 6, 8]));
+        assert_eq!(pruned(&sudoku, 8, 7), HashSet::from([7]));
+        assert_eq!(pruned(&sudoku, 5, 4), HashSet::from([1, 2, 3, 5, 7, 8, 9]));
+    }
+
+    #[test]
+    fn solve_kropki() -> Result<(), anyhow::Error> {
+        let sudoku = solves("tests/kropki")?;
+        assert!(sudoku.constraints().iter().any(|c| c.name() == "kropki"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,591 @@
+use std::collections::hash_set::HashSet;
+
+use crate::sudoku::*;
+use crate::sudoku::chains::Chain;
+
+// Ways a person takes numbers out of the cells, from the simplest (see `rank`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Technique {
+    // the only number left for a cell
+    NakedSingle,
+    // the only cell left for a number in a house
+    HiddenSingle,
+    // the number can only go where a region meets another house, so it's out
+    // of the rest of that house
+    Pointing,
+    // the same, but for a row, column or other house and the region (or any
+    // house) that has all its places for the number
+    BoxLine,
+    // `size` cells of a house with only `size` numbers between them, which
+    // are out of the rest of the house
+    NakedSubset { size: usize },
+    // `size` numbers of a house that only fit in `size` cells, which can't
+    // have anything else
+    HiddenSubset { size: usize },
+    // a number that only fits in `size` columns of `size` rows (or the other
+    // way around), which is out of the rest of those columns: X-Wing,
+    // Swordfish and Jellyfish
+    Fish { size: usize },
+    // the same with extra places (the fins) in a region, which only take the
+    // number out of that region. Sashimi when a row has one place left
+    // without the fins.
+    FinnedFish { size: usize, sashimi: bool },
+    // four cells of two rows, two columns and two regions where the same two
+    // numbers could be swapped unless the rest of one of them is true, of
+    // `kind` 1 to 4. Only for puzzles with one solution.
+    UniqueRectangle { kind: u8 },
+    // every empty cell but one has two numbers, so the one in three places
+    // of its houses goes in that cell. Only for puzzles with one solution.
+    BugPlusOne,
+    // a cell with two numbers that sees two others with one of them each and
+    // a third one they share, which is out of the cells that see both
+    XYWing,
+    // the same with the third number in the middle cell too, so the cells
+    // that lose it have to see all three
+    XYZWing,
+    // two cells with the same two numbers, joined by a house where one of
+    // them only has two places, each seeing one of the cells
+    WWing,
+    // chains of strong and weak links between places of one number
+    XChain,
+    // chains through cells with two numbers
+    XYChain,
+    // chains with links of both kinds, where one of the ends has to be true
+    Aic,
+    // a chain that closes on itself, so every weak link in it is strong too
+    NiceLoop,
+    // the places of a number joined by houses where it only has two, in two
+    // colors where one is all true: a color seeing itself is false, and
+    // cells seeing both colors can't have the number
+    SimpleColoring,
+    // the same between two groups of colors that see each other
+    MultiColoring,
+    // every number of a cell, tried with the simpler techniques, takes out
+    // the same candidates (or breaks the puzzle)
+    CellForcingChain,
+    // the same for every place of a number in a house
+    UnitForcingChain
+}
+
+impl Technique {
+    pub const HARDEST: Technique = Technique::UnitForcingChain;
+
+    // Whether it takes out numbers because they'd leave more than one
+    // solution, which is wrong for puzzles that might have more
+    pub fn needs_uniqueness(self) -> bool {
+        matches!(self, Technique::UniqueRectangle { .. } | Technique::BugPlusOne)
+    }
+
+    // Where it goes in the order the solver tries them. Subsets go by size
+    // first, as a hidden pair is easier to spot than a naked quad.
+    fn rank(self) -> (usize, usize, usize) {
+        match self {
+            Technique::NakedSingle => (0, 0, 0),
+            Technique::HiddenSingle => (1, 0, 0),
+            Technique::Pointing => (2, 0, 0),
+            Technique::BoxLine => (3, 0, 0),
+            Technique::NakedSubset { size } => (4, size, 0),
+            Technique::HiddenSubset { size } => (4, size, 1),
+            Technique::Fish { size } => (5, size, 0),
+            Technique::FinnedFish { size, sashimi } => (6, size, sashimi as usize),
+            Technique::UniqueRectangle { kind } => (7, kind as usize, 0),
+            Technique::BugPlusOne => (8, 0, 0),
+            Technique::XYWing => (9, 0, 0),
+            Technique::XYZWing => (10, 0, 0),
+            Technique::WWing => (11, 0, 0),
+            Technique::XChain => (12, 0, 0),
+            Technique::XYChain => (13, 0, 0),
+            Technique::Aic => (14, 0, 0),
+            Technique::NiceLoop => (15, 0, 0),
+            Technique::SimpleColoring => (16, 0, 0),
+            Technique::MultiColoring => (17, 0, 0),
+            Technique::CellForcingChain => (18, 0, 0),
+            Technique::UnitForcingChain => (19, 0, 0)
+        }
+    }
+}
+
+impl Ord for Technique {
+    fn cmp(&self, other: &Technique) -> std::cmp::Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+impl PartialOrd for Technique {
+    fn partial_cmp(&self, other: &Technique) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+type Find = fn(&mut Candidates, &Sudoku) -> Option<Deduction>;
+
+// The techniques that take out candidates, in the order `step` tries them
+const ELIMINATIONS: [(Technique, Find); 29] = [
+    (Technique::Pointing, |c, s| c.locked_candidates(s, Technique::Pointing)),
+    (Technique::BoxLine, |c, s| c.locked_candidates(s, Technique::BoxLine)),
+    (Technique::NakedSubset { size: 2 }, |c, s| c.naked_subset(s, 2)),
+    (Technique::HiddenSubset { size: 2 }, |c, s| c.hidden_subset(s, 2)),
+    (Technique::NakedSubset { size: 3 }, |c, s| c.naked_subset(s, 3)),
+    (Technique::HiddenSubset { size: 3 }, |c, s| c.hidden_subset(s, 3)),
+    (Technique::NakedSubset { size: 4 }, |c, s| c.naked_subset(s, 4)),
+    (Technique::HiddenSubset { size: 4 }, |c, s| c.hidden_subset(s, 4)),
+    (Technique::Fish { size: 2 }, |c, s| c.fish(s, 2)),
+    (Technique::Fish { size: 3 }, |c, s| c.fish(s, 3)),
+    (Technique::Fish { size: 4 }, |c, s| c.fish(s, 4)),
+    (Technique::FinnedFish { size: 2, sashimi: false }, |c, s| c.finned_fish(s, 2)),
+    (Technique::FinnedFish { size: 3, sashimi: false }, |c, s| c.finned_fish(s, 3)),
+    (Technique::FinnedFish { size: 4, sashimi: false }, |c, s| c.finned_fish(s, 4)),
+    (Technique::UniqueRectangle { kind: 1 }, |c, s| c.unique_rectangle(s, 1)),
+    (Technique::UniqueRectangle { kind: 2 }, |c, s| c.unique_rectangle(s, 2)),
+    (Technique::UniqueRectangle { kind: 3 }, |c, s| c.unique_rectangle(s, 3)),
+    (Technique::UniqueRectangle { kind: 4 }, |c, s| c.unique_rectangle(s, 4)),
+    (Technique::BugPlusOne, |c, s| c.bug_plus_one(s)),
+    (Technique::XYWing, |c, s| c.xy_wing(s)),
+    (Technique::XYZWing, |c, s| c.xyz_wing(s)),
+    (Technique::WWing, |c, s| c.w_wing(s)),
+    (Technique::XChain, |c, s| c.chain(s, Chain::X)),
+    (Technique::XYChain, |c, s| c.chain(s, Chain::XY)),
+    (Technique::Aic, |c, s| c.chain(s, Chain::Alternating)),
+    (Technique::SimpleColoring, |c, s| c.simple_coloring(s)),
+    (Technique::MultiColoring, |c, s| c.multi_coloring(s)),
+    (Technique::CellForcingChain, |c, s| c.cell_forcing_chain(s)),
+    (Technique::UnitForcingChain, |c, s| c.unit_forcing_chain(s))
+];
+
+// What a step found and why, so it can be shown. Singles fill the cell in
+// `cells` with the number in `digits`, the others take out `eliminations`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Deduction {
+    pub technique: Technique,
+    // indexes in the houses of the sudoku of the ones it's about, like the
+    // house with the subset, or the base and cover lines of a fish
+    pub houses: Vec<usize>,
+    pub cells: Vec<(usize, usize)>,
+    pub digits: Vec<u8>,
+    // (row, col, number)
+    pub eliminations: Vec<(usize, usize, u8)>,
+    // the candidates of a wing or chain in order, joined by strong and weak
+    // links in turns starting with a strong one
+    pub chain: Vec<(usize, usize, u8)>
+}
+
+impl Deduction {
+    fn single(technique: Technique, houses: Vec<usize>, (row, col): (usize, usize), n: u8) -> Deduction {
+        Deduction {
+            technique,
+            houses,
+            cells: vec![(row, col)],
+            digits: vec![n],
+            eliminations: Vec::new(),
+            chain: Vec::new()
+        }
+    }
+}
+
+// The numbers that can still go in every empty cell, which the techniques take
+// out one step at a time. Cells with a number have none.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidates {
+    cells: Vec<Vec<HashSet<u8>>>,
+    // the puzzle is known to have one solution, so the techniques that need
+    // it can be used
+    unique: bool
+}
+
+impl Candidates {
+    pub fn new(sudoku: &Sudoku) -> Candidates {
+        let size = sudoku.size();
+        let mut cells = vec![vec![HashSet::new(); size]; size];
+
+        for (row, col, n) in sudoku.iterate() {
+            if *n == Number::Empty {
+                cells[row][col] = sudoku.candidates(row, col);
+            }
+        }
+
+        Candidates { cells, unique: false }
+    }
+
+    // The candidates of the board with only the numbers `keep` leaves, to set
+    // up a technique in the tests
+    #[cfg(test)]
+    pub(super) fn keeping(sudoku: &Sudoku, keep: impl Fn((usize, usize), u8) -> bool) -> Candidates {
+        let mut candidates = Candidates::new(sudoku);
+
+        for (row, line) in candidates.cells.iter_mut().enumerate() {
+            for (col, numbers) in line.iter_mut().enumerate() {
+                numbers.retain(|&n| keep((row, col), n));
+            }
+        }
+
+        candidates
+    }
+
+    // With these cells down to a few numbers
+    #[cfg(test)]
+    pub(super) fn with_cells(sudoku: &Sudoku, cells: &[((usize, usize), &[u8])]) -> Candidates {
+        Candidates::keeping(sudoku, |cell, n| {
+            cells.iter().find(|(other, _)| *other == cell).is_none_or(|(_, numbers)| numbers.contains(&n))
+        })
+    }
+
+    pub fn set_unique(&mut self, unique: bool) {
+        self.unique = unique;
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> &HashSet<u8> {
+        &self.cells[row][col]
+    }
+
+    // Steps until no technique up to `hardest` gets anywhere, and returns what
+    // each step found
+    pub fn propagate(&mut self, sudoku: &mut Sudoku, hardest: Technique) -> Vec<Deduction> {
+        std::iter::from_fn(|| self.step(sudoku, hardest)).collect()
+    }
+
+    // One step of the simplest technique up to `hardest` that gets somewhere
+    pub fn step(&mut self, sudoku: &mut Sudoku, hardest: Technique) -> Option<Deduction> {
+        if let Some(deduction) = self.naked_single(sudoku) {
+            return Some(deduction);
+        }
+
+        if hardest >= Technique::HiddenSingle {
+            if let Some(deduction) = self.hidden_single(sudoku) {
+                return Some(deduction);
+            }
+        }
+
+        let unique = self.unique;
+        ELIMINATIONS.iter()
+            .filter(|(technique, _)| *technique <= hardest)
+            .filter(|(technique, _)| unique || !technique.needs_uniqueness())
+            .find_map(|(_, find)| find(self, sudoku))
+    }
+
+    // Puts the number in the sudoku and prunes the empty cells again, as it
+    // leaves less room in its houses and maybe in the constraints
+    pub(super) fn place(&mut self, sudoku: &mut Sudoku, row: usize, col: usize, n: u8) {
+        sudoku.insert_number(row, col, n);
+        self.cells[row][col].clear();
+
+        for (i, j, number) in sudoku.iterate() {
+            if *number == Number::Empty {
+                let allowed = sudoku.candidates(i, j);
+                self.cells[i][j].retain(|n| allowed.contains(n));
+            }
+        }
+    }
+
+    // Takes the numbers out of the cells, keeping the ones that were there
+    pub(super) fn eliminate(
+        &mut self,
+        targets: impl IntoIterator<Item = (usize, usize, u8)>
+    ) -> Vec<(usize, usize, u8)> {
+        targets.into_iter()
+            .filter(|&(row, col, n)| self.cells[row][col].remove(&n))
+            .collect()
+    }
+
+    fn naked_single(&mut self, sudoku: &mut Sudoku) -> Option<Deduction> {
+        let (row, col, n) = sudoku.iterate()
+            .filter(|(_, _, n)| **n == Number::Empty)
+            .find_map(|(row, col, _)| match self.cells[row][col].iter().collect::<Vec<_>>()[..] {
+                [&n] => Some((row, col, n)),
+                _ => None
+            })?;
+
+        self.place(sudoku, row, col, n);
+
+        Some(Deduction::single(Technique::NakedSingle, Vec::new(), (row, col), n))
+    }
+
+    // The empty cells of the house where the number can go, unless it's
+    // already there. Only for houses with room for every number, as smaller
+    // ones (like most cages) don't need all of them.
+    pub(super) fn places(&self, sudoku: &Sudoku, house: &[(usize, usize)], n: u8) -> Option<Vec<(usize, usize)>> {
+        if house.len() != sudoku.digits()
+            || house.iter().any(|&(row, col)| sudoku.check_position(row, col).value() == Some(n))
+        {
+            return None;
+        }
+
+        Some(house.iter().copied().filter(|&(row, col)| self.cells[row][col].contains(&n)).collect())
+    }
+
+    fn hidden_single(&mut self, sudoku: &mut Sudoku) -> Option<Deduction> {
+        let (house, (row, col), n) = sudoku.houses().iter().enumerate()
+            .find_map(|(i, house)| (1..=sudoku.digits() as u8).find_map(|n| {
+                match self.places(sudoku, house, n).as_deref() {
+                    Some(&[cell]) => Some((i, cell, n)),
+                    _ => None
+                }
+            }))?;
+
+        self.place(sudoku, row, col, n);
+
+        Some(Deduction::single(Technique::HiddenSingle, vec![house], (row, col), n))
+    }
+
+    // Pointing and box/line reduction are the same thing from both sides: all
+    // the places for a number in one house are in another, so the number can
+    // go nowhere else in that other house. Pointing starts from the regions,
+    // box/line reduction from the other houses.
+    fn locked_candidates(&mut self, sudoku: &Sudoku, technique: Technique) -> Option<Deduction> {
+        for (i, house) in sudoku.houses().iter().enumerate() {
+            if sudoku.is_region(i) != (technique == Technique::Pointing) {
+                continue;
+            }
+
+            for n in 1..=sudoku.digits() as u8 {
+                let Some(places) = self.places(sudoku, house, n) else {
+                    continue;
+                };
+                let Some(&(row, col)) = places.first() else {
+                    continue;
+                };
+
+                for &other in sudoku.houses_of(row, col) {
+                    let other_cells = &sudoku.houses()[other];
+
+                    if other == i || !places.iter().all(|cell| other_cells.contains(cell)) {
+                        continue;
+                    }
+
+                    let eliminations = self.eliminate(other_cells.iter()
+                        .filter(|cell| !places.contains(cell))
+                        .map(|&(row, col)| (row, col, n)));
+
+                    if !eliminations.is_empty() {
+                        return Some(Deduction {
+                            technique,
+                            houses: vec![i, other],
+                            cells: places,
+                            digits: vec![n],
+                            eliminations,
+                            chain: Vec::new()
+                        });
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    // `size` cells of a house whose candidates are `size` numbers between
+    // them, so those numbers go in those cells and nowhere else in the house
+    fn naked_subset(&mut self, sudoku: &Sudoku, size: usize) -> Option<Deduction> {
+        for (i, house) in sudoku.houses().iter().enumerate() {
+            let open: Vec<_> = house.iter().copied()
+                .filter(|&(row, col)| (2..=size).contains(&self.cells[row][col].len()))
+                .collect();
+
+            for cells in combinations(&open, size) {
+                let mut digits: Vec<u8> = cells.iter()
+                    .flat_map(|&(row, col)| self.cells[row][col].iter().copied())
+                    .collect::<HashSet<_>>()
+                    .into_iter()
+                    .collect();
+
+                if digits.len() != size {
+                    continue;
+                }
+
+                digits.sort_unstable();
+
+                let eliminations = self.eliminate(house.iter()
+                    .filter(|cell| !cells.contains(cell))
+                    .flat_map(|&(row, col)| digits.iter().map(move |&n| (row, col, n))));
+
+                if !eliminations.is_empty() {
+                    let technique = Technique::NakedSubset { size };
+                    return Some(Deduction {
+                        technique,
+                        houses: vec![i],
+                        cells,
+                        digits,
+                        eliminations,
+                        chain: Vec::new()
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    // `size` numbers of a house that only fit in `size` cells between them, so
+    // those cells can't have any other number
+    fn hidden_subset(&mut self, sudoku: &Sudoku, size: usize) -> Option<Deduction> {
+        for (i, house) in sudoku.houses().iter().enumerate() {
+            let places: Vec<(u8, Vec<_>)> = (1..=sudoku.digits() as u8)
+                .filter_map(|n| Some((n, self.places(sudoku, house, n)?)))
+                .filter(|(_, places)| (2..=size).contains(&places.len()))
+                .collect();
+
+            for subset in combinations(&places, size) {
+                let mut cells: Vec<_> = subset.iter()
+                    .flat_map(|(_, places)| places.iter().copied())
+                    .collect::<HashSet<_>>()
+                    .into_iter()
+                    .collect();
+
+                if cells.len() != size {
+                    continue;
+                }
+
+                cells.sort_unstable();
+                let digits: Vec<u8> = subset.iter().map(|&(n, _)| n).collect();
+
+                let eliminations = self.eliminate(cells.iter()
+                    .flat_map(|&(row, col)| self.cells[row][col].iter().map(move |&n| (row, col, n)))
+                    .filter(|(_, _, n)| !digits.contains(n))
+                    .collect::<Vec<_>>());
+
+                if !eliminations.is_empty() {
+                    let technique = Technique::HiddenSubset { size };
+                    return Some(Deduction {
+                        technique,
+                        houses: vec![i],
+                        cells,
+                        digits,
+                        eliminations,
+                        chain: Vec::new()
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+// Every way to pick `k` of the items, keeping their order
+pub(super) fn combinations<T: Clone>(items: &[T], k: usize) -> Vec<Vec<T>> {
+    if k == 0 {
+        return vec![Vec::new()];
+    }
+
+    (0..items.len())
+        .flat_map(|i| combinations(&items[i + 1..], k - 1).into_iter().map(move |mut rest| {
+            rest.insert(0, items[i].clone());
+            rest
+        }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn techniques_in_order() {
+        assert!(ELIMINATIONS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(Technique::HiddenSubset { size: 2 } < Technique::NakedSubset { size: 3 });
+        assert!(Technique::NakedSubset { size: 4 } < Technique::Fish { size: 2 });
+        assert!(ELIMINATIONS.iter().all(|&(technique, _)| technique <= Technique::HARDEST));
+    }
+
+    #[test]
+    fn hidden_single() {
+        // 1 is in rows 1 and 2 and columns 2 and 3, which leaves the first
+        // column of the top left box
+        let mut sudoku = Sudoku::new(9);
+        sudoku.insert_given(0, 4, 1);
+        sudoku.insert_given(1, 7, 1);
+        sudoku.insert_given(4, 1, 1);
+        sudoku.insert_given(7, 2, 1);
+
+        let mut candidates = Candidates::new(&sudoku);
+        assert_eq!(candidates.get(2, 0).len(), 9);
+
+        assert_eq!(candidates.step(&mut sudoku, Technique::HARDEST).map(|d| d.technique), Some(Technique::HiddenSingle));
+        assert_eq!(sudoku.check_position(2, 0), Number::Answer(1));
+        assert!(candidates.get(2, 0).is_empty());
+        assert!(!candidates.get(2, 5).contains(&1));
+    }
+
+    #[test]
+    fn locked_candidates() {
+        // the rest of the top left box is full, so its 1 is in the first row
+        // and out of the rest of that row
+        let mut sudoku = Sudoku::new(9);
+        for (i, n) in (0..6).zip(2..) {
+            sudoku.insert_given(1 + i / 3, i % 3, n);
+        }
+
+        let mut candidates = Candidates::new(&sudoku);
+        assert_eq!(candidates.step(&mut sudoku, Technique::HARDEST).map(|d| d.technique), Some(Technique::Pointing));
+        assert!(candidates.get(0, 0).contains(&1));
+        assert!(!candidates.get(0, 8).contains(&1));
+
+        // 1 of the first row can only be in the top left box, so it's out of
+        // the other rows of the box
+        let mut sudoku = Sudoku::new(9);
+        for col in 3..9 {
+            sudoku.insert_given(0, col, col as u8);
+        }
+
+        let mut candidates = Candidates::new(&sudoku);
+        assert_eq!(candidates.step(&mut sudoku, Technique::Pointing), None);
+        assert_eq!(candidates.step(&mut sudoku, Technique::HARDEST).map(|d| d.technique), Some(Technique::BoxLine));
+        assert!(candidates.get(0, 1).contains(&1));
+        assert!(!candidates.get(1, 1).contains(&1));
+    }
+
+    #[test]
+    fn naked_pair() {
+        let sudoku = Sudoku::new(9);
+        let mut candidates = Candidates::new(&sudoku);
+        candidates.cells[0][0] = HashSet::from([1, 2]);
+        candidates.cells[0][1] = HashSet::from([1, 2]);
+
+        let deduction = candidates.naked_subset(&sudoku, 2).unwrap();
+        assert_eq!(deduction.technique, Technique::NakedSubset { size: 2 });
+        assert_eq!(deduction.houses, vec![0]);
+        assert_eq!(deduction.cells, vec![(0, 0), (0, 1)]);
+        assert_eq!(deduction.digits, vec![1, 2]);
+        assert_eq!(deduction.eliminations.len(), 7 * 2);
+        assert_eq!(candidates.get(0, 5), &HashSet::from_iter(3..=9));
+
+        // the pair is in the top left box too
+        let deduction = candidates.naked_subset(&sudoku, 2).unwrap();
+        assert!(sudoku.is_region(deduction.houses[0]));
+        assert_eq!(deduction.eliminations.len(), 6 * 2);
+        assert!(candidates.naked_subset(&sudoku, 2).is_none());
+    }
+
+    #[test]
+    fn hidden_triple() {
+        // 1, 2 and 3 of the first column only fit in its first three cells
+        let sudoku = Sudoku::new(9);
+        let mut candidates = Candidates::new(&sudoku);
+        for row in 3..9 {
+            candidates.cells[row][0].retain(|&n| n > 3);
+        }
+        candidates.cells[2][0].remove(&3);
+
+        assert!(candidates.hidden_subset(&sudoku, 2).is_none());
+
+        let deduction = candidates.hidden_subset(&sudoku, 3).unwrap();
+        assert_eq!(deduction.technique, Technique::HiddenSubset { size: 3 });
+        assert_eq!(deduction.houses, vec![9]);
+        assert_eq!(deduction.cells, vec![(0, 0), (1, 0), (2, 0)]);
+        assert_eq!(deduction.digits, vec![1, 2, 3]);
+        assert_eq!(deduction.eliminations.len(), 3 * 6);
+        assert_eq!(candidates.get(2, 0), &HashSet::from([1, 2]));
+    }
+
+    #[test]
+    fn propagate_solves_without_guessing() -> Result<(), anyhow::Error> {
+        let mut sudoku = Sudoku::from_file(std::path::Path::new("tests/example"))?;
+        let deductions = Candidates::new(&sudoku).propagate(&mut sudoku, Technique::HARDEST);
+
+        // a single for each of the 49 empty cells
+        assert_eq!(deductions.len(), 49);
+        assert!(sudoku.iterate().all(|(_, _, n)| *n != Number::Empty));
+        assert!(sudoku.check_rules().is_empty());
+
+        Ok(())
+    }
+}
@@ -1,7 +1,33 @@
+#[allow(clippy::module_inception)]
 mod sudoku;
 mod solver;
 mod generator;
+mod constraint;
+mod killer;
+mod houses;
+mod thermo;
+mod arrow;
+mod kropki;
+mod outside;
+mod chess;
+mod logic;
+mod fish;
+mod chains;
+mod uniqueness;
+mod coloring;
+mod forcing;
+mod batch;
+mod dimacs;
 
 pub use sudoku::*;
-pub use solver::*;
-pub use generator::*;
+pub use constraint::*;
+pub use killer::Cage;
+pub use houses::ExtraHouse;
+pub use thermo::Thermometer;
+pub use arrow::Arrow;
+pub use kropki::{Marker, Markers};
+pub use chess::Chess;
+pub use logic::{Candidates, Technique};
+pub use dimacs::{Cnf, Error as ModelError};
+pub use batch::{read_puzzles, run_batch, Mode, Rating, Report};
+pub use outside::{LittleKiller, Sandwich, Skyscraper};
@@ -0,0 +1,406 @@
+use std::collections::hash_set::HashSet;
+
+use crate::sudoku::*;
+
+// Clues written outside of the grid, at `clue`, which is a cell position that
+// can be one off the board on any side. They are about the cells in a line
+// from the clue, the closest one first.
+
+// The numbers between the smallest and the largest number of a row or column
+// add up to `sum`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sandwich {
+    pub sum: usize,
+    pub clue: (isize, isize),
+    pub cells: Vec<(usize, usize)>
+}
+
+// Seeing the cells as buildings as tall as their numbers, `count` of them can
+// be seen from the clue, as the shorter ones hide behind taller ones
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Skyscraper {
+    pub count: usize,
+    pub clue: (isize, isize),
+    pub cells: Vec<(usize, usize)>
+}
+
+// The numbers along a diagonal add up to `sum`, and can repeat
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LittleKiller {
+    pub sum: usize,
+    pub clue: (isize, isize),
+    pub cells: Vec<(usize, usize)>
+}
+
+// The cells from the clue going `step` each time, until the end of the board,
+// or none when it misses the board
+pub fn ray(size: usize, clue: (isize, isize), step: (isize, isize)) -> Vec<(usize, usize)> {
+    let inside = |n: isize| (0..size as isize).contains(&n);
+
+    (1..=size as isize + 1)
+        .map(|i| (clue.0 + step.0 * i, clue.1 + step.1 * i))
+        .skip_while(|&(row, col)| !inside(row) || !inside(col))
+        .take_while(|&(row, col)| inside(row) && inside(col))
+        .take(size)
+        .map(|(row, col)| (row as usize, col as usize))
+        .collect()
+}
+
+// Towards the board from a clue just off one of its sides
+pub fn inwards(size: usize, (row, col): (isize, isize)) -> Option<(isize, isize)> {
+    let size = size as isize;
+    let inside = |n: isize| (0..size).contains(&n);
+
+    match (row, col) {
+        (-1, col) if inside(col) => Some((1, 0)),
+        (row, -1) if inside(row) => Some((0, 1)),
+        (row, col) if row == size && inside(col) => Some((-1, 0)),
+        (row, col) if col == size && inside(row) => Some((0, -1)),
+        _ => None
+    }
+}
+
+fn clue_text(clue: (isize, isize), text: String) -> Shape {
+    Shape::Text {
+        center: (clue.0 as f32 + 0.5, clue.1 as f32 + 0.5),
+        text,
+        size: 0.4,
+        ink: Ink::Given
+    }
+}
+
+impl Sandwich {
+    pub fn new(size: usize, sum: usize, clue: (isize, isize)) -> Option<Sandwich> {
+        let cells = ray(size, clue, inwards(size, clue)?);
+
+        Some(Sandwich { sum, clue, cells })
+    }
+
+    // Where the smallest and largest numbers are, in order
+    fn crusts(&self, sudoku: &Sudoku) -> Option<(usize, usize)> {
+        let find = |n: usize| self.cells.iter()
+            .position(|&(row, col)| sudoku.check_position(row, col).value() == Some(n as u8));
+
+        let a = find(1)?;
+        let b = find(sudoku.digits())?;
+
+        Some((a.min(b), a.max(b)))
+    }
+}
+
+impl Constraint for Sandwich {
+    fn check(&self, sudoku: &Sudoku, wrong_numbers: &mut HashSet<(usize, usize)>) {
+        let Some((first, last)) = self.crusts(sudoku) else {
+            return;
+        };
+
+        let between = &self.cells[first + 1..last];
+        let values: Vec<_> = between.iter()
+            .map(|&(row, col)| sudoku.check_position(row, col).value())
+            .collect();
+        let sum: usize = values.iter().flatten().map(|&n| n as usize).sum();
+        let full = values.iter().all(Option::is_some);
+
+        if sum > self.sum || (full && sum != self.sum) {
+            wrong_numbers.insert(self.cells[first]);
+            wrong_numbers.insert(self.cells[last]);
+            wrong_numbers.extend(between.iter().copied());
+        }
+    }
+
+    fn prune(&self, sudoku: &Sudoku, row: usize, col: usize, candidates: &mut HashSet<u8>) {
+        let Some(k) = self.cells.iter().position(|&cell| cell == (row, col)) else {
+            return;
+        };
+        let Some((first, last)) = self.crusts(sudoku) else {
+            return;
+        };
+
+        // only the cells between the crusts, which need a 2 at least
+        if k <= first || k >= last {
+            return;
+        }
+
+        let between = &self.cells[first + 1..last];
+        let filled: usize = between.iter()
+            .filter_map(|&(i, j)| sudoku.check_position(i, j).value())
+            .map(|n| n as usize)
+            .sum();
+        let empty = between.iter().filter(|&&(i, j)| sudoku.check_position(i, j) == Number::Empty).count();
+        let room = self.sum.saturating_sub(filled + 2 * (empty - 1));
+
+        candidates.retain(|&n| (n as usize) <= room && (empty > 1 || n as usize == room));
+    }
+
+    // How many crusts there have been, and the sum after the first one, which
+    // stops one past `sum` as that's already too much
+    fn encode(&self, sudoku: &Sudoku, cnf: &mut Cnf) {
+        let sum = self.sum;
+        let is_crust = |n: u8| n == 1 || n as usize == sudoku.digits();
+
+        cnf.automaton(
+            &self.cells,
+            (0, 0),
+            |&(crusts, partial): &(u8, usize), n| Some(match crusts {
+                0 if is_crust(n) => (1, 0),
+                1 if is_crust(n) => (2, partial),
+                1 => (1, (partial + n as usize).min(sum + 1)),
+                _ => (crusts, partial)
+            }),
+            |&(crusts, partial)| crusts < 2 || partial == sum
+        );
+    }
+
+    fn shapes(&self, _size: usize) -> Vec<Shape> {
+        vec![clue_text(self.clue, self.sum.to_string())]
+    }
+
+    fn regenerate(&self) -> Regenerate {
+        Regenerate::Unsupported
+    }
+
+    fn outside(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "sandwich"
+    }
+}
+
+impl Skyscraper {
+    pub fn new(size: usize, count: usize, clue: (isize, isize)) -> Option<Skyscraper> {
+        let cells = ray(size, clue, inwards(size, clue)?);
+
+        Some(Skyscraper { count, clue, cells })
+    }
+}
+
+impl Constraint for Skyscraper {
+    // Counts the ones seen up to the first empty cell, which can only grow
+    // unless the tallest is already there
+    fn check(&self, sudoku: &Sudoku, wrong_numbers: &mut HashSet<(usize, usize)>) {
+        let mut seen = 0;
+        let mut tallest = 0;
+
+        for &(row, col) in &self.cells {
+            let Some(n) = sudoku.check_position(row, col).value() else {
+                break;
+            };
+
+            if n > tallest {
+                seen += 1;
+                tallest = n;
+            }
+        }
+
+        if seen > self.count || (tallest as usize == sudoku.digits() && seen != self.count) {
+            wrong_numbers.extend(self.cells.iter().copied());
+        }
+    }
+
+    // The k-th cell from the clue can't be so tall that there's no room for
+    // the rest to be seen
+    fn prune(&self, sudoku: &Sudoku, row: usize, col: usize, candidates: &mut HashSet<u8>) {
+        let Some(k) = self.cells.iter().position(|&cell| cell == (row, col)) else {
+            return;
+        };
+
+        let highest = (sudoku.digits() + 1 + k).saturating_sub(self.count);
+
+        candidates.retain(|&n| (n as usize) <= highest);
+    }
+
+    // The tallest building so far and how many can be seen, never more than
+    // `count`
+    fn encode(&self, sudoku: &Sudoku, cnf: &mut Cnf) {
+        let count = self.count;
+        let digits = sudoku.digits() as u8;
+
+        cnf.automaton(
+            &self.cells,
+            (0, 0),
+            |&(tallest, seen): &(u8, usize), n| if n > tallest {
+                (seen < count).then_some((n, seen + 1))
+            } else {
+                Some((tallest, seen))
+            },
+            |&(tallest, seen)| tallest != digits || seen == count
+        );
+    }
+
+    fn shapes(&self, _size: usize) -> Vec<Shape> {
+        vec![clue_text(self.clue, self.count.to_string())]
+    }
+
+    fn regenerate(&self) -> Regenerate {
+        Regenerate::Unsupported
+    }
+
+    fn outside(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "skyscraper"
+    }
+}
+
+impl LittleKiller {
+    // The clue has to be off the board, like the ones of the other outside
+    // clues, but it can be at a corner
+    pub fn new(size: usize, sum: usize, clue: (isize, isize), step: (isize, isize)) -> Option<LittleKiller> {
+        let inside = |n: isize| (0..size as isize).contains(&n);
+        let diagonal = step.0.abs() == 1 && step.1.abs() == 1;
+        let cells = ray(size, clue, step);
+
+        (!(inside(clue.0) && inside(clue.1)) && diagonal && !cells.is_empty())
+            .then_some(LittleKiller { sum, clue, cells })
+    }
+}
+
+impl Constraint for LittleKiller {
+    fn check(&self, sudoku: &Sudoku, wrong_numbers: &mut HashSet<(usize, usize)>) {
+        let (sum, empty) = filled(sudoku, &self.cells);
+
+        if sum + empty > self.sum || (empty == 0 && sum != self.sum) {
+            wrong_numbers.extend(self.cells.iter().copied());
+        }
+    }
+
+    fn prune(&self, sudoku: &Sudoku, row: usize, col: usize, candidates: &mut HashSet<u8>) {
+        if !self.cells.contains(&(row, col)) {
+            return;
+        }
+
+        // the clue is the whole sum, so the last empty cell of the diagonal
+        // gets exactly what's missing
+        let (sum, empty) = filled(sudoku, &self.cells);
+        let room = room(self.sum, sum, empty);
+
+        candidates.retain(|&n| (n as usize) <= room && (empty > 1 || n as usize == room));
+    }
+
+    // The sums so far along the diagonal, where numbers can repeat
+    fn encode(&self, _sudoku: &Sudoku, cnf: &mut Cnf) {
+        let sum = self.sum;
+
+        cnf.automaton(
+            &self.cells,
+            0,
+            |&partial: &usize, n| Some(partial + n as usize).filter(|&partial| partial <= sum),
+            |&partial| partial == sum
+        );
+    }
+
+    // The sum with a short arrow pointing along the diagonal
+    fn shapes(&self, _size: usize) -> Vec<Shape> {
+        let (row, col) = self.cells[0];
+        let center = (self.clue.0 as f32 + 0.5, self.clue.1 as f32 + 0.5);
+        let d_row = (row as f32 + 0.5 - center.0) * 0.3;
+        let d_col = (col as f32 + 0.5 - center.1) * 0.3;
+        let from = (center.0 + d_row, center.1 + d_col);
+
+        vec![
+            clue_text(self.clue, self.sum.to_string()),
+            Shape::Line { from, to: (from.0 + d_row, from.1 + d_col), width: 0.04, ink: Ink::Given }
+        ]
+    }
+
+    fn regenerate(&self) -> Regenerate {
+        Regenerate::Unsupported
+    }
+
+    fn outside(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "little"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rays() {
+        assert_eq!(ray(4, (-1, 2), (1, 0)), vec![(0, 2), (1, 2), (2, 2), (3, 2)]);
+        assert_eq!(ray(4, (1, 4), (0, -1)), vec![(1, 3), (1, 2), (1, 1), (1, 0)]);
+        assert_eq!(ray(4, (-1, 1), (1, 1)), vec![(0, 2), (1, 3)]);
+        assert_eq!(ray(4, (-1, 3), (-1, 1)), vec![]);
+        assert_eq!(inwards(4, (4, 0)), Some((-1, 0)));
+        assert_eq!(inwards(4, (-1, -1)), None);
+        assert_eq!(inwards(4, (2, 2)), None);
+    }
+
+    #[test]
+    fn sandwich() {
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(Sandwich::new(9, 10, (0, -1)).unwrap());
+
+        sudoku.insert_number(0, 1, 1);
+        sudoku.insert_number(0, 2, 4);
+        sudoku.insert_number(0, 5, 9);
+        assert!(sudoku.check_rules().is_empty());
+
+        // 6 left for two cells, and the last one has to be what's left
+        assert_eq!(pruned(&sudoku, 0, 3), HashSet::from_iter(1..=4));
+        sudoku.insert_number(0, 3, 2);
+        assert_eq!(pruned(&sudoku, 0, 4), HashSet::from([4]));
+        assert_eq!(pruned(&sudoku, 0, 7), HashSet::from_iter(1..=9));
+
+        sudoku.insert_number(0, 4, 5);
+        assert_eq!(
+            sudoku.check_rules(),
+            HashSet::from([(0, 1), (0, 2), (0, 3), (0, 4), (0, 5)])
+        );
+    }
+
+    #[test]
+    fn skyscraper() {
+        let mut sudoku = Sudoku::new(9);
+        // looking up column 1 from below
+        sudoku.add_constraint(Skyscraper::new(9, 3, (9, 0)).unwrap());
+
+        assert_eq!(pruned(&sudoku, 8, 0), HashSet::from_iter(1..=7));
+        assert_eq!(pruned(&sudoku, 6, 0), HashSet::from_iter(1..=9));
+
+        sudoku.insert_number(8, 0, 2);
+        sudoku.insert_number(7, 0, 5);
+        sudoku.insert_number(6, 0, 3);
+        assert!(sudoku.check_rules().is_empty());
+
+        // 2, 5 and 9 are seen, and nothing else can be
+        sudoku.insert_number(5, 0, 9);
+        assert!(sudoku.check_rules().is_empty());
+        sudoku.insert_number(5, 0, 6);
+        sudoku.insert_number(4, 0, 9);
+        assert_eq!(sudoku.check_rules().len(), 9);
+    }
+
+    #[test]
+    fn little_killer() {
+        let mut sudoku = Sudoku::new(9);
+        // down and left from above the last column: r1c8, r2c7 ... r8c1
+        sudoku.add_constraint(LittleKiller::new(9, 12, (-1, 8), (1, -1)).unwrap());
+        assert!(LittleKiller::new(9, 12, (-1, 8), (1, 0)).is_none());
+        assert!(LittleKiller::new(9, 12, (2, 2), (1, 1)).is_none());
+
+        assert_eq!(pruned(&sudoku, 0, 7), HashSet::from([1, 2, 3, 4, 5]));
+
+        sudoku.insert_number(0, 7, 5);
+        assert!(sudoku.check_rules().is_empty());
+        sudoku.insert_number(0, 7, 6);
+        assert_eq!(sudoku.check_rules().len(), 8);
+    }
+
+    #[test]
+    fn solve_outside_clues() -> Result<(), anyhow::Error> {
+        let sudoku = solves("tests/outside")?;
+        assert!(sudoku.constraints().iter().all(|c| c.outside()));
+
+        Ok(())
+    }
+}
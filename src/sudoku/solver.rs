@@ -3,231 +3,162 @@ use std::collections::hash_set::HashSet;
 use crate::sudoku::*;
 
 impl Sudoku {
+    // Goes as far as the techniques get, and only guesses the rest when they
+    // get stuck
     pub fn solve(&self) -> Option<Sudoku> {
-        let mut solutions = Vec::new();
-        let now = std::time::Instant::now();
-
-        self.recursive_solve(0, 0, &mut solutions);
-
-        let elapsed = now.elapsed();
-        println!("solved in {}.{:03}s.",
-            elapsed.as_secs(),
-            elapsed.subsec_millis()
-        );
-
-        solutions.first().map( |sol| sol.clone() )
+        self.solve_guessing().0
     }
 
-    fn recursive_solve(
-        &self,
-        row: usize,
-        col: usize,
-        solutions: &mut Vec<Sudoku>
-    ) {
-        let size = self.size() as u8;
+    // The solution, and whether the techniques got stuck so it took guessing
+    pub fn solve_guessing(&self) -> (Option<Sudoku>, bool) {
+        let mut solutions = Vec::new();
 
         let mut aux = self.clone();
-        aux.solve_by_naked_singles();
-
-        match aux.check_position(row, col) {
-            Number::Answer(_) | Number::Given(_) => {
-                aux.next_recursion(row, col, solutions);
-            }
-            Number::Empty => {
-                aux.solve_by_naked_singles();
-                for i in 1..=size {
-                    aux.insert_number(row, col, i);
-
-                    if !aux.check_rules().is_empty() {
-                        continue;
-                    }
+        Candidates::new(&aux).propagate(&mut aux, Technique::HARDEST);
 
-                    aux.next_recursion(row, col, solutions);
-                }
+        let guessed = aux.iterate().any(|(_, _, n)| *n == Number::Empty);
+        if !guessed {
+            if aux.check_rules().is_empty() {
+                solutions.push(aux);
             }
-        }
-    }
-
-    fn next_recursion(
-        &self,
-        row: usize,
-        col: usize,
-        solutions: &mut Vec<Sudoku>
-    ) {
-        let size = self.size();
-
-        // if it reaches the end of the sudoku, push the solution
-        if size - 1 == row && size - 1 == col {
-            solutions.push(self.clone());
         } else {
-            if col == size - 1 {
-                self.recursive_solve(row + 1, 0, solutions);
-            } else {
-                self.recursive_solve(row, col + 1, solutions);
-            }
+            aux.search(1, &mut |_| {}, &mut solutions);
         }
+
+        (solutions.into_iter().next(), guessed)
     }
 
-    pub fn check_rules(&self) -> HashSet<(usize, usize)> {
-        let mut wrong_numbers = HashSet::new();
-        let size = self.size();
+    // How many solutions there are, stopping at `limit`, so a limit of 2 tells
+    // whether a puzzle has a unique solution
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        let mut solutions = Vec::new();
 
-        for i in 0..size {
-            for j in 0..size {
-                self.check_row(i, j, &mut wrong_numbers);
-                self.check_column(i, j, &mut wrong_numbers);
-                self.check_box(i, j, &mut wrong_numbers);
-            }
-        }
+        self.clone().search(limit, &mut |_| {}, &mut solutions);
 
-        wrong_numbers
+        solutions.len()
     }
 
-    fn check_row(
-        &self,
-        row: usize,
-        col: usize,
-        wrong_numbers: &mut HashSet<(usize, usize)>
+    // Guesses the empty cell with the fewest candidates, trying them in the
+    // order `order` leaves them, until there are `limit` solutions. The guesses
+    // are made on the board itself and taken back after. The candidates only
+    // come from the houses of the cell and the pruning of the constraints, so
+    // the whole board is only checked once it's full.
+    pub(super) fn search(
+        &mut self,
+        limit: usize,
+        order: &mut dyn FnMut(&mut Vec<u8>),
+        solutions: &mut Vec<Sudoku>
     ) {
-        let pos = self.check_position(row, col);
+        let mut fewest: Option<((usize, usize), HashSet<u8>)> = None;
 
-        for i in 0..self.size() {
-            if col == i {
+        for (row, col, n) in self.iterate() {
+            if *n != Number::Empty {
                 continue;
             }
 
-            if pos.compare(self.check_position(row, i)) {
-                wrong_numbers.insert((row, col));
-                wrong_numbers.insert((row, i));
+            let candidates = self.candidates(row, col);
+            if fewest.as_ref().is_none_or(|(_, best)| candidates.len() < best.len()) {
+                let forced = candidates.len() <= 1;
+                fewest = Some(((row, col), candidates));
+
+                if forced {
+                    break;
+                }
             }
         }
-    }
-
-    fn check_column(
-        &self,
-        row: usize,
-        col: usize,
-        wrong_numbers: &mut HashSet<(usize, usize)>
-    ) {
-        let pos = self.check_position(row, col);
 
-        for i in 0..self.size() {
-            if row == i {
-                continue;
+        // full, but the pruning of some constraints doesn't catch everything
+        let Some(((row, col), candidates)) = fewest else {
+            if self.check_rules().is_empty() {
+                solutions.push(self.clone());
             }
+            return;
+        };
+
+        let mut numbers: Vec<u8> = candidates.into_iter().collect();
+        numbers.sort_unstable();
+        order(&mut numbers);
 
-            if pos.compare(self.check_position(i, col)) {
-                wrong_numbers.insert((row, col));
-                wrong_numbers.insert((i, col));
+        for n in numbers {
+            if solutions.len() >= limit {
+                break;
             }
+
+            self.insert_number(row, col, n);
+            self.search(limit, order, solutions);
         }
+
+        self.delete_number(row, col);
     }
 
-    fn check_box(
-        &self,
-        row: usize,
-        col: usize,
-        wrong_numbers: &mut HashSet<(usize, usize)>
-    ) {
-        let pos = self.check_position(row, col);
+    pub fn check_rules(&self) -> HashSet<(usize, usize)> {
+        let mut wrong_numbers = HashSet::new();
 
-        for i in (row / 3 * 3)..(row / 3 * 3 + 3) {
-            for j in (col / 3 * 3)..(col / 3 * 3 + 3) {
-                if row == i && col == j {
-                    continue;
-                }
+        for house in self.houses() {
+            for (i, &(row, col)) in house.iter().enumerate() {
+                let pos = self.check_position(row, col);
 
-                if pos.compare(self.check_position(i, j)) {
-                    wrong_numbers.insert((row, col));
-                    wrong_numbers.insert((i, j));
+                for &(other_row, other_col) in &house[i + 1..] {
+                    if pos.compare(self.check_position(other_row, other_col)) {
+                        wrong_numbers.insert((row, col));
+                        wrong_numbers.insert((other_row, other_col));
+                    }
                 }
             }
         }
-    }
-
-    fn solve_by_naked_singles(&mut self) {
-        let total_numbers = HashSet::from_iter(1..=(self.size() as u8));
-        let mut const_row: Vec<_> = (0..self.size()).map(|i| self.constraints_row(i)).collect();
-        let mut const_col: Vec<_> = (0..self.size()).map(|i| self.constraints_col(i)).collect();
-        let mut const_box: Vec<_> = (0..self.size()).map(|i| self.constraints_box(i)).collect();
-
-        let size = self.size();
-        let mut stop = false;
-
-        while stop == false {
-            stop = true;
-            for i in 0..size {
-                for j in 0..size {
-                    if self.check_position(i, j) != Number::Empty {
-                        continue;
-                    }
 
-                    let box_number = self.box_number(i, j);
+        for constraint in self.constraints() {
+            constraint.check(self, &mut wrong_numbers);
+        }
 
-                    let possible = &total_numbers - &const_row[i];
-                    let possible = &possible - &const_col[j];
-                    let possible = &possible - &const_box[box_number];
+        wrong_numbers
+    }
 
-                    if possible.len() != 1 {
-                        continue;
-                    }
+    // Numbers that can go in the empty cell: not in any of its houses, and
+    // allowed by every constraint
+    pub(super) fn candidates(&self, row: usize, col: usize) -> HashSet<u8> {
+        // one bit per number, so the houses don't go through the set
+        let mut used = 0u64;
 
-                    for number in possible.iter() {
-                        self.insert_number(i, j, *number);
-                        const_row[i] = self.constraints_row(i);
-                        const_col[j] = self.constraints_col(j);
-                        const_box[box_number] = self.constraints_box(box_number);
-                        stop = false; // Only stop if there is nothing to insert
-                    }
+        for &house in self.houses_of(row, col) {
+            for &(i, j) in &self.houses()[house] {
+                if let Number::Answer(n) | Number::Given(n) = self.check_position(i, j) {
+                    used |= 1 << n;
                 }
             }
         }
-    }
 
-    fn constraints_row(&self, row: usize) -> HashSet<u8> {
-        let mut constraints = HashSet::with_capacity(self.size());
+        let mut possible: HashSet<u8> = (1..=(self.digits() as u8))
+            .filter(|&n| used & (1 << n) == 0)
+            .collect();
 
-        for i in 0..self.size() {
-            match self.check_position(row, i) {
-                Number::Empty => continue,
-                Number::Answer(n) | Number::Given(n) => {
-                    constraints.insert(n);
-                }
-            }
+        for constraint in self.constraints() {
+            constraint.prune(self, row, col, &mut possible);
         }
 
-        constraints
+        possible
     }
+}
 
-    fn constraints_col(&self, col: usize) -> HashSet<u8> {
-        let mut constraints = HashSet::with_capacity(self.size());
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        for i in 0..self.size() {
-            match self.check_position(i, col) {
-                Number::Empty => continue,
-                Number::Answer(n) | Number::Given(n) => {
-                    constraints.insert(n);
-                }
-            }
-        }
-
-        constraints
-    }
+    #[test]
+    fn search_puts_the_board_back() -> Result<(), anyhow::Error> {
+        let mut sudoku = Sudoku::from_file(std::path::Path::new("tests/escargot"))?;
+        let puzzle = sudoku.clone();
+        let mut solutions = Vec::new();
 
-    fn constraints_box(&self, box_: usize) -> HashSet<u8> {
-        let mut constraints = HashSet::with_capacity(self.size());
+        sudoku.search(2, &mut |_| {}, &mut solutions);
+        assert_eq!(solutions.len(), 1);
+        assert!(solutions[0].check_rules().is_empty());
+        assert_eq!(sudoku, puzzle);
 
-        for i in (box_ / 3 * 3)..(box_ / 3 * 3 + 3) { // rows
-            for j in (box_ % 3 * 3)..(box_ % 3 * 3 + 3) { // columns
-                match self.check_position(i, j) {
-                    Number::Empty => continue,
-                    Number::Answer(n) | Number::Given(n) => {
-                        constraints.insert(n);
-                    }
-                }
-            }
-        }
+        // without a given it has more than one
+        sudoku.delete_position(0, 0);
+        assert_eq!(sudoku.count_solutions(2), 2);
 
-        constraints
+        Ok(())
     }
 }
@@ -2,15 +2,34 @@ use std::path::Path;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
+use std::sync::Arc;
+use crate::sudoku::{
+    Arrow, Cage, Chess, Constraint, Diagonals, ExtraHouse, LittleKiller, Marker, Markers, Regenerate,
+    Sandwich, Skyscraper, Thermometer
+};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("I/O Error")]
     Io(#[from] std::io::Error),
     #[error("Created grid has an invalid size. rows: {rows:?}, columns: {cols:?}")]
-    MismatchedGrid { rows: usize, cols: usize }
+    MismatchedGrid { rows: usize, cols: usize },
+    #[error("Line {line}: can't read {text:?}")]
+    InvalidLine { line: usize, text: String },
+    #[error("Regions must split the grid in {size} parts of {size} cells each")]
+    InvalidRegions { size: usize },
+    #[error("Grids of {digits}x{digits} must fit in the board and line up with their boxes")]
+    InvalidGrids { digits: usize },
+    #[error("A thermometer of {cells} cells can't go up from 1 to {digits}")]
+    LongThermometer { cells: usize, digits: usize },
+    #[error("Can't generate a new puzzle with {name} clues")]
+    CantRegenerate { name: &'static str }
 }
 
+// The region of the cells that aren't in any grid, like the corners between
+// the grids of a Samurai
+const HOLE: usize = usize::MAX;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Number {
     Empty,
@@ -34,31 +53,165 @@ impl Number {
             }
         }
     }
+
+    pub fn value(self) -> Option<u8> {
+        match self {
+            Number::Empty => None,
+            Number::Given(n) | Number::Answer(n) => Some(n)
+        }
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Sudoku {
-    rows: Vec<Vec<Number>>
+    rows: Vec<Vec<Number>>,
+    // numbers go from 1 to this, the size of each grid
+    digits: usize,
+    // top left cell of every grid on the board, more than one when they
+    // overlap like in Samurai
+    grids: Vec<(usize, usize)>,
+    // the region ("box") of every cell, [row][col], or HOLE
+    region_of: Vec<Vec<usize>>,
+    // the cells of every region
+    regions: Vec<Vec<(usize, usize)>>,
+    constraints: Vec<Arc<dyn Constraint>>,
+    // rows, columns, regions and the houses of the constraints, with the
+    // houses every cell is in, [row][col]
+    houses: Vec<Vec<(usize, usize)>>,
+    houses_of: Vec<Vec<Vec<usize>>>,
+    // how many of the houses are rows, columns and regions
+    grid_houses: usize
+}
+
+// Constraints are the same if they are the same object, they are never changed
+// after being added
+impl PartialEq for Sudoku {
+    fn eq(&self, other: &Sudoku) -> bool {
+        self.rows == other.rows
+            && self.grids == other.grids
+            && self.region_of == other.region_of
+            && self.constraints.len() == other.constraints.len()
+            && self.constraints.iter()
+                .zip(&other.constraints)
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+    }
 }
 
+impl Eq for Sudoku {}
+
 impl Sudoku {
     pub fn new(size: usize) -> Sudoku {
-        let rows = vec![vec![Number::Empty;size];size];
+        let mut sudoku = Sudoku {
+            rows: vec![vec![Number::Empty;size];size],
+            digits: size,
+            grids: Vec::new(),
+            region_of: Vec::new(),
+            regions: Vec::new(),
+            constraints: Vec::new(),
+            houses: Vec::new(),
+            houses_of: Vec::new(),
+            grid_houses: 0
+        };
+
+        // a single grid can't be wrong
+        let _ = sudoku.set_grids(size, vec![(0, 0)]);
+
+        sudoku
+    }
+
+    // Puts grids of `digits` cells a side on the board, each with its usual
+    // boxes, for puzzles of overlapping grids like Samurai or twodoku. Where
+    // they overlap the boxes have to be the same.
+    pub fn set_grids(&mut self, digits: usize, grids: Vec<(usize, usize)>) -> Result<(), Error> {
+        let size = self.size();
+
+        // boxes as square as possible, wider than taller
+        let height = (1..=digits).filter(|h| h * h <= digits && digits.is_multiple_of(*h)).max().unwrap_or(1);
+        let width = digits / height.max(1);
+
+        let fits = |&(top, left): &(usize, usize)| {
+            top + digits <= size
+                && left + digits <= size
+                && top.is_multiple_of(height)
+                && left.is_multiple_of(width)
+        };
+
+        if grids.is_empty() || !grids.iter().all(fits) {
+            return Err(Error::InvalidGrids { digits });
+        }
 
-        Sudoku { rows }
+        // boxes are numbered by where they are on the board, and then
+        // renumbered to leave out the ones no grid has
+        let mut region_of = vec![vec![HOLE; size]; size];
+        for &(top, left) in &grids {
+            for (row, line) in region_of.iter_mut().enumerate().skip(top).take(digits) {
+                for (col, region) in line.iter_mut().enumerate().skip(left).take(digits) {
+                    *region = row / height * size + col / width;
+                }
+            }
+        }
+
+        let mut numbers: Vec<usize> = region_of.iter().flatten().copied().filter(|&r| r != HOLE).collect();
+        numbers.sort();
+        numbers.dedup();
+
+        let mut regions = vec![Vec::with_capacity(digits); numbers.len()];
+        for (row, line) in region_of.iter_mut().enumerate() {
+            for (col, region) in line.iter_mut().enumerate() {
+                if *region != HOLE {
+                    *region = numbers.binary_search(region).unwrap_or(0);
+                    regions[*region].push((row, col));
+                }
+            }
+        }
+
+        self.digits = digits;
+        self.grids = grids;
+        self.region_of = region_of;
+        self.regions = regions;
+        self.update_houses();
+
+        Ok(())
     }
 
+    // The file has the grid as comma separated rows, followed by lines that add
+    // extra rules to it, starting with the name of the rule:
+    //
+    //     cage <sum> r1c1 r1c2 ...
+    //     diagonals
+    //     region r1c1 r1c2 ...
+    //     house r1c1 r1c2 ...
+    //     windoku
+    //     thermo <bulb> r1c2 ...
+    //     arrow <circle> r1c2 ...
+    //     white|black|x|v r1c1 r1c2
+    //     negative white black ...
+    //     grids <digits> r1c1 r7c7 ...
+    //
+    // When there are region lines they replace the boxes, and between all of
+    // them they must cover the grid. Dots and X/V go between adjacent cells,
+    // and a negative line says that every dot (or X, V) of that kind is given.
+    // A grids line splits the board into grids with their top left cells,
+    // and the cells outside of them are left out of the puzzle.
     pub fn from_file(path: &Path) -> Result<Sudoku, Error> {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
 
-        let rows: Vec<Vec<_>> = reader.lines().filter_map(|l| l.ok()).map( |line|
+        let lines: Vec<_> = reader.lines()
+            .map_while(Result::ok)
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .collect();
+
+        let (rules, grid): (Vec<_>, Vec<_>) = lines.into_iter()
+            .partition(|(_, line)| line.starts_with(|c: char| c.is_ascii_alphabetic()));
+
+        let rows: Vec<Vec<_>> = grid.into_iter().map( |(_, line)|
             line.split(',')
                 .map(|n|
-                    u8::from_str_radix(n, 10)
-                        .and_then(|n|
-                            Ok(Number::Given(n))
-                        ).unwrap_or(Number::Empty)
+                    n.trim().parse::<u8>()
+                        .map(Number::Given)
+                        .unwrap_or(Number::Empty)
                 ).collect()
         ).collect();
 
@@ -70,14 +223,314 @@ impl Sudoku {
             }
         }
 
-        Ok(Sudoku { rows } )
+        let mut sudoku = Sudoku::new(rows.len());
+        sudoku.rows = rows;
+
+        let mut pending = PendingRules::default();
+
+        for (i, line) in rules {
+            sudoku.read_rule(&line, &mut pending)
+                .ok_or(Error::InvalidLine { line: i + 1, text: line.clone() })?;
+        }
+
+        let PendingRules { grids, regions, markers, longest_thermometer } = pending;
+
+        if let Some((digits, grids)) = grids {
+            sudoku.set_grids(digits, grids)?;
+        }
+
+        if longest_thermometer > sudoku.digits() {
+            return Err(Error::LongThermometer { cells: longest_thermometer, digits: sudoku.digits() });
+        }
+
+        if !regions.is_empty() {
+            let size = sudoku.size();
+            let mut region_of = vec![vec![usize::MAX; size]; size];
+
+            for (i, cells) in regions.iter().enumerate() {
+                for &(row, col) in cells {
+                    region_of[row][col] = i;
+                }
+            }
+
+            sudoku.set_regions(region_of)?;
+        }
+
+        if !markers.is_empty() {
+            sudoku.add_constraint(markers);
+        }
+
+        Ok(sudoku)
+    }
+
+    // Puzzle collections have a puzzle a line, with the numbers of the rows one
+    // after the other and '.' or '0' for the empty cells, like
+    // "53..7....6..195...". Only for plain boards of 4 to 9 numbers.
+    pub fn from_line(line: &str) -> Option<Sudoku> {
+        let line = line.trim();
+        let size = (4..=9).find(|size| size * size == line.len())?;
+        let mut sudoku = Sudoku::new(size);
+
+        for (i, c) in line.chars().enumerate() {
+            if c == '.' || c == '0' {
+                continue;
+            }
+
+            let n = c.to_digit(10).filter(|&n| n as usize <= size)?;
+            sudoku.insert_given(i / size, i % size, n as u8);
+        }
+
+        Some(sudoku)
+    }
+
+    // The other way around, with '.' for the empty cells
+    pub fn to_line(&self) -> String {
+        self.iterate()
+            .map(|(_, _, n)| n.value().map_or('.', |n| char::from(b'0' + n)))
+            .collect()
+    }
+
+    fn read_rule(&mut self, line: &str, pending: &mut PendingRules) -> Option<()> {
+        let mut words = line.split_whitespace();
+
+        match words.next()? {
+            "cage" => {
+                let sum = words.next()?.parse().ok()?;
+                let cells = words.map(|w| self.parse_cell(w)).collect::<Option<Vec<_>>>()?;
+
+                if cells.is_empty() {
+                    return None;
+                }
+
+                self.add_constraint(Cage::new(sum, cells));
+            }
+            "diagonals" => {
+                self.add_constraint(Diagonals);
+            }
+            "house" => {
+                let cells = words.map(|w| self.parse_cell(w)).collect::<Option<Vec<_>>>()?;
+                self.add_constraint(ExtraHouse::new(cells));
+            }
+            "windoku" => {
+                for window in ExtraHouse::windows(self.size()) {
+                    self.add_constraint(window);
+                }
+            }
+            "thermo" => {
+                let cells = words.map(|w| self.parse_cell(w)).collect::<Option<Vec<_>>>()?;
+
+                if cells.len() < 2 {
+                    return None;
+                }
+
+                pending.longest_thermometer = pending.longest_thermometer.max(cells.len());
+                self.add_constraint(Thermometer::new(cells));
+            }
+            "arrow" => {
+                let circle = self.parse_cell(words.next()?)?;
+                let cells = words.map(|w| self.parse_cell(w)).collect::<Option<Vec<_>>>()?;
+
+                if cells.is_empty() {
+                    return None;
+                }
+
+                self.add_constraint(Arrow::new(circle, cells));
+            }
+            name @ ("white" | "black" | "x" | "v") => {
+                let marker = Marker::from_name(name)?;
+                let a = self.parse_cell(words.next()?)?;
+                let b = self.parse_cell(words.next()?)?;
+
+                if words.next().is_some() || !pending.markers.add(a, b, marker) {
+                    return None;
+                }
+            }
+            "negative" => {
+                for word in words {
+                    pending.markers.add_negative(Marker::from_name(word)?);
+                }
+            }
+            name @ ("antiknight" | "antiking") => {
+                self.add_constraint(Chess::from_name(name)?);
+            }
+            "sandwich" => {
+                let sum = words.next()?.parse().ok()?;
+                let clue = self.parse_outside(words.next()?)?;
+                self.add_constraint(Sandwich::new(self.size(), sum, clue)?);
+            }
+            "skyscraper" => {
+                let count = words.next()?.parse().ok()?;
+                let clue = self.parse_outside(words.next()?)?;
+                self.add_constraint(Skyscraper::new(self.size(), count, clue)?);
+            }
+            "little" => {
+                let sum = words.next()?.parse().ok()?;
+                let clue = self.parse_outside(words.next()?)?;
+                let step = match words.next()? {
+                    "dr" => (1, 1),
+                    "dl" => (1, -1),
+                    "ur" => (-1, 1),
+                    "ul" => (-1, -1),
+                    _ => return None
+                };
+
+                self.add_constraint(LittleKiller::new(self.size(), sum, clue, step)?);
+            }
+            "grids" => {
+                let digits = words.next()?.parse().ok()?;
+                let grids = words.map(|w| self.parse_cell(w)).collect::<Option<Vec<_>>>()?;
+
+                pending.grids = Some((digits, grids));
+            }
+            "region" => {
+                pending.regions.push(words.map(|w| self.parse_cell(w)).collect::<Option<Vec<_>>>()?);
+            }
+            _ => return None
+        }
+
+        Some(())
+    }
+
+    pub fn add_constraint<C: Constraint + 'static>(&mut self, constraint: C) {
+        self.constraints.push(Arc::new(constraint));
+        self.update_houses();
+    }
+
+    // Removes the constraints with the same name, or adds it if there were
+    // none, and tells whether it's there now
+    pub fn toggle_constraint<C: Constraint + 'static>(&mut self, constraint: C) -> bool {
+        let before = self.constraints.len();
+        self.constraints.retain(|c| c.name() != constraint.name());
+
+        if self.constraints.len() == before {
+            self.add_constraint(constraint);
+            return true;
+        }
+
+        self.update_houses();
+
+        false
+    }
+
+    pub fn constraints(&self) -> &[Arc<dyn Constraint>] {
+        &self.constraints
+    }
+
+    // Every group of cells that can't repeat a number
+    pub fn houses(&self) -> &[Vec<(usize, usize)>] {
+        &self.houses
+    }
+
+    // Indexes in `houses` of the houses the cell is in
+    pub fn houses_of(&self, row: usize, col: usize) -> &[usize] {
+        &self.houses_of[row][col]
+    }
+
+    // Whether the house is one of the regions (boxes or jigsaw pieces), which
+    // come after the rows and columns of every grid
+    pub fn is_region(&self, house: usize) -> bool {
+        (2 * self.digits * self.grids.len()..self.grid_houses).contains(&house)
+    }
+
+    // Indexes in `houses` of the rows and of the columns of one of the grids
+    pub fn lines(&self, grid: usize) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let first = 2 * self.digits * grid;
+
+        (first..first + self.digits, first + self.digits..first + 2 * self.digits)
+    }
+
+    fn update_houses(&mut self) {
+        let size = self.size();
+        let digits = self.digits;
+
+        let lines = self.grids.iter().flat_map(|&(top, left)| {
+            let rows = (top..top + digits).map(move |row| (left..left + digits).map(|col| (row, col)).collect());
+            let cols = (left..left + digits).map(move |col| (top..top + digits).map(|row| (row, col)).collect());
+
+            rows.chain(cols)
+        });
+
+        self.houses = lines.chain(self.regions.iter().cloned()).collect();
+        self.grid_houses = self.houses.len();
+        self.houses.extend(self.constraints.iter().flat_map(|c| c.houses(size)));
+        self.houses_of = vec![vec![Vec::new(); size]; size];
+
+        for (i, house) in self.houses.iter().enumerate() {
+            for &(row, col) in house {
+                self.houses_of[row][col].push(i);
+            }
+        }
+    }
+
+    // An empty grid with the same rules for the whole grid, but without the
+    // numbers and clues of this puzzle
+    pub fn blank_copy(&self) -> Sudoku {
+        let mut sudoku = Sudoku::new(self.size());
+        sudoku.digits = self.digits;
+        sudoku.grids = self.grids.clone();
+        sudoku.region_of = self.region_of.clone();
+        sudoku.regions = self.regions.clone();
+        sudoku.constraints = self.constraints.iter()
+            .filter(|c| matches!(c.regenerate(), Regenerate::Keep | Regenerate::KeepUnique))
+            .cloned()
+            .collect();
+        sudoku.update_houses();
+
+        sudoku
+    }
+
+    // Reads a cell written as r<row>c<col>, counting from 1
+    pub(super) fn parse_cell(&self, text: &str) -> Option<(usize, usize)> {
+        let (row, col) = text.strip_prefix('r')?.split_once('c')?;
+        let row = row.parse::<usize>().ok()?.checked_sub(1)?;
+        let col = col.parse::<usize>().ok()?.checked_sub(1)?;
+
+        (row < self.size() && col < self.size()).then_some((row, col))
+    }
+
+    // Like a cell, but it can be one off the board on any side, like r0c3 for
+    // above the third column, where the clues outside the grid go
+    fn parse_outside(&self, text: &str) -> Option<(isize, isize)> {
+        let (row, col) = text.strip_prefix('r')?.split_once('c')?;
+        let row = row.parse::<isize>().ok()?;
+        let col = col.parse::<isize>().ok()?;
+        let edge = self.size() as isize + 1;
+
+        ((0..=edge).contains(&row) && (0..=edge).contains(&col)).then_some((row - 1, col - 1))
     }
 
+    // Cells a side of the whole board
     pub fn size(&self) -> usize {
         self.rows.len()
     }
 
+    pub fn digits(&self) -> usize {
+        self.digits
+    }
+
+    pub fn grids(&self) -> &[(usize, usize)] {
+        &self.grids
+    }
+
+    // Whether the cell is in some grid, and not in the space between them
+    pub fn in_puzzle(&self, row: usize, col: usize) -> bool {
+        self.region_of.get(row)
+            .and_then(|line| line.get(col))
+            .is_some_and(|&region| region != HOLE)
+    }
+
+    // Whether the cells share a row, column or region of a grid
+    pub fn are_peers(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        self.houses_of(a.0, a.1).iter()
+            .take_while(|&&house| house < self.grid_houses)
+            .any(|house| self.houses_of(b.0, b.1).contains(house))
+    }
+
     pub fn insert_given(&mut self, row: usize, col: usize, number: u8) {
+        if !self.in_puzzle(row, col) {
+            return;
+        }
+
         if let Some(val) = self.rows[row].get_mut(col) {
             *val = Number::Given(number);
         }
@@ -90,6 +543,10 @@ impl Sudoku {
     }
 
     pub fn insert_number(&mut self, row: usize, col: usize, answer: u8) {
+        if !self.in_puzzle(row, col) {
+            return;
+        }
+
         if let Some(val) = self.rows[row].get_mut(col) {
             match val {
                 Number::Empty | Number::Answer(..) => { *val = Number::Answer(answer); }
@@ -100,43 +557,101 @@ impl Sudoku {
 
     pub fn delete_number(&mut self, row: usize, col: usize) {
         if let Some(val) = self.rows[row].get_mut(col) {
-            match val {
-                Number::Answer(..) => { *val = Number::Empty; }
-                _ => {}
+            if let Number::Answer(..) = val {
+                *val = Number::Empty;
             }
         }
     }
 
+    // The same puzzle with only the given numbers
+    pub fn without_answers(&self) -> Sudoku {
+        let mut sudoku = self.clone();
+
+        for row in sudoku.rows.iter_mut() {
+            for val in row.iter_mut() {
+                if let Number::Answer(_) = val {
+                    *val = Number::Empty;
+                }
+            }
+        }
+
+        sudoku
+    }
+
     pub fn check_position(&self, row: usize, col: usize) -> Number {
         self.rows.get(row)
-            .map(|c| c.get(col))
-            .flatten()
-            .map(|val| *val)
+            .and_then(|c| c.get(col))
+            .copied()
             .unwrap_or(Number::Empty)
     }
 
-    pub fn iterate(&self) -> SudokuIter {
-        SudokuIter::new(&self.rows)
+    pub fn iterate(&self) -> SudokuIter<'_> {
+        SudokuIter::new(&self.rows, &self.region_of)
+    }
+
+    // Replaces the boxes with other regions, given the region number of every
+    // cell. There must be as many regions as rows, each with as many cells.
+    pub fn set_regions(&mut self, region_of: Vec<Vec<usize>>) -> Result<(), Error> {
+        let size = self.size();
+        let mut regions = vec![Vec::with_capacity(size); size];
+
+        if region_of.len() != size || self.grids.len() != 1 {
+            return Err(Error::InvalidRegions { size });
+        }
+
+        for (row, line) in region_of.iter().enumerate() {
+            if line.len() != size {
+                return Err(Error::InvalidRegions { size });
+            }
+
+            for (col, &region) in line.iter().enumerate() {
+                regions.get_mut(region)
+                    .ok_or(Error::InvalidRegions { size })?
+                    .push((row, col));
+            }
+        }
+
+        if regions.iter().any(|cells| cells.len() != size) {
+            return Err(Error::InvalidRegions { size });
+        }
+
+        self.region_of = region_of;
+        self.regions = regions;
+        self.update_houses();
+
+        Ok(())
     }
 
     pub fn box_number(&self, row: usize, col: usize) -> usize {
-        // boxes are in this order, it should start in 1 but idk
+        // with the usual boxes they are in this order
         // 0 1 2
         // 3 4 5
         // 6 7 8
-        row / 3 * 3 + col / 3
+        self.region_of[row][col]
     }
 }
 
+// Rules of a file that are read as a whole once every line is in
+#[derive(Default)]
+struct PendingRules {
+    grids: Option<(usize, Vec<(usize, usize)>)>,
+    regions: Vec<Vec<(usize, usize)>>,
+    markers: Markers,
+    // checked once the grids say how many numbers there are
+    longest_thermometer: usize
+}
+
+// Goes through the cells of the grids, skipping the holes between them
 pub struct SudokuIter<'a> {
     rows: &'a Vec<Vec<Number>>,
+    region_of: &'a Vec<Vec<usize>>,
     i: usize,
     j: usize
 }
 
 impl<'a> SudokuIter<'a> {
-    fn new(rows: &Vec<Vec<Number>>) -> SudokuIter {
-       SudokuIter { rows, i: 0, j: 0 }
+    fn new(rows: &'a Vec<Vec<Number>>, region_of: &'a Vec<Vec<usize>>) -> SudokuIter<'a> {
+       SudokuIter { rows, region_of, i: 0, j: 0 }
     }
 }
 
@@ -148,15 +663,19 @@ impl<'a> Iterator for SudokuIter<'a> {
             if let Some(val) = row.get(self.j) {
                 self.j += 1;
 
-                return Some((self.i, self.j - 1,  val));
+                if self.region_of[self.i][self.j - 1] == HOLE {
+                    return self.next();
+                }
+
+                Some((self.i, self.j - 1,  val))
             } else {
                 self.i += 1;
                 self.j = 0;
 
-                return self.next();
+                self.next()
             }
         } else {
-            return None;
+            None
         }
     }
 }
@@ -165,13 +684,14 @@ impl<'a> Iterator for SudokuIter<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sudoku::solves;
 
     #[test]
     fn load_file() -> Result<(), anyhow::Error> {
         let path = std::path::Path::new("tests/example");
         let sudoku = Sudoku::from_file(path)?;
 
-        assert_eq!(sudoku.check_position(0, 3), Some(&Number::Given(3)));
+        assert_eq!(sudoku.check_position(0, 3), Number::Given(3));
 
         Ok(())
     }
@@ -182,7 +702,7 @@ mod tests {
 
         sudoku.insert_number(0, 0, 9);
 
-        assert_eq!(sudoku.check_position(0, 0), Some(&Number::Answer(9)));
+        assert_eq!(sudoku.check_position(0, 0), Number::Answer(9));
 
         Ok(())
     }
@@ -193,7 +713,7 @@ mod tests {
 
         sudoku.insert_number(2, 3, 5);
 
-        assert_eq!(sudoku.check_position(2, 3), Some(&sudoku.rows[2][3]));
+        assert_eq!(sudoku.check_position(2, 3), sudoku.rows[2][3]);
 
         Ok(())
     }
@@ -216,5 +736,109 @@ mod tests {
         
         Ok(())
     }
-}
 
+    #[test]
+    fn default_boxes() {
+        let sudoku = Sudoku::new(9);
+        assert_eq!(sudoku.box_number(4, 7), 5);
+        assert_eq!(sudoku.regions[8].len(), 9);
+
+        // 2 rows and 3 columns each
+        let sudoku = Sudoku::new(6);
+        assert_eq!(sudoku.box_number(1, 2), 0);
+        assert_eq!(sudoku.box_number(2, 3), 3);
+    }
+
+    #[test]
+    fn load_jigsaw() -> Result<(), anyhow::Error> {
+        let sudoku = Sudoku::from_file(std::path::Path::new("tests/jigsaw"))?;
+
+        assert_eq!(sudoku.box_number(0, 2), 1);
+        assert_eq!(sudoku.box_number(2, 8), 5);
+        assert!(sudoku.regions[5].contains(&(2, 8)));
+
+        let solution = sudoku.solve().ok_or(anyhow::anyhow!("no solution"))?;
+        assert!(solution.check_rules().is_empty());
+        assert!(solution.iterate().all(|(_, _, n)| *n != Number::Empty));
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_regions() {
+        let mut sudoku = Sudoku::new(4);
+
+        // region 0 has 5 cells and region 3 has 3
+        let regions = vec![
+            vec![0, 0, 1, 1],
+            vec![0, 0, 1, 1],
+            vec![2, 2, 3, 0],
+            vec![2, 2, 3, 3]
+        ];
+        assert!(matches!(
+            sudoku.set_regions(regions),
+            Err(Error::InvalidRegions { size: 4 })
+        ));
+    }
+
+    #[test]
+    fn overlapping_grids() {
+        // twodoku, sharing the bottom right box of the first grid
+        let mut sudoku = Sudoku::new(15);
+        sudoku.set_grids(9, vec![(0, 0), (6, 6)]).unwrap();
+
+        assert_eq!(sudoku.digits(), 9);
+        assert_eq!(sudoku.iterate().count(), 2 * 81 - 9);
+        assert!(!sudoku.in_puzzle(0, 14));
+        assert_eq!(sudoku.box_number(7, 7), sudoku.box_number(8, 8));
+
+        // the same row of the board, but in different grids
+        sudoku.insert_number(7, 0, 4);
+        sudoku.insert_number(7, 14, 4);
+        assert!(sudoku.check_rules().is_empty());
+
+        // the shared box is in both rows
+        sudoku.insert_number(7, 7, 4);
+        assert_eq!(
+            sudoku.check_rules(),
+            std::collections::HashSet::from([(7, 0), (7, 14), (7, 7)])
+        );
+        assert!(sudoku.are_peers((6, 6), (14, 6)));
+        assert!(!sudoku.are_peers((0, 0), (14, 14)));
+
+        // nothing goes in the holes
+        sudoku.insert_given(0, 14, 1);
+        assert_eq!(sudoku.check_position(0, 14), Number::Empty);
+
+        assert!(matches!(
+            sudoku.set_grids(9, vec![(0, 0), (7, 6)]),
+            Err(Error::InvalidGrids { digits: 9 })
+        ));
+    }
+
+    #[test]
+    fn read_little_killers() {
+        let mut sudoku = Sudoku::new(9);
+        let mut pending = PendingRules::default();
+
+        assert!(sudoku.read_rule("little 26 r0c2 dr", &mut pending).is_some());
+        assert!(sudoku.read_rule("little 35 r10c1 ur", &mut pending).is_some());
+        assert!(sudoku.read_rule("little 9 r10c10 ul", &mut pending).is_some());
+        assert_eq!(sudoku.constraints().len(), 3);
+
+        // on the board, going nowhere, or off the corner without a diagonal
+        assert!(sudoku.read_rule("little 12 r3c3 dr", &mut pending).is_none());
+        assert!(sudoku.read_rule("little 12 r0c2 up", &mut pending).is_none());
+        assert!(sudoku.read_rule("little 12 r0c10 ur", &mut pending).is_none());
+        assert_eq!(sudoku.constraints().len(), 3);
+    }
+
+    #[test]
+    fn solve_samurai() -> Result<(), anyhow::Error> {
+        let sudoku = solves("tests/samurai")?;
+        assert_eq!(sudoku.size(), 21);
+        assert_eq!(sudoku.grids().len(), 5);
+
+        Ok(())
+    }
+}
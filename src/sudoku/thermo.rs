@@ -0,0 +1,167 @@
+use std::collections::hash_set::HashSet;
+
+use crate::sudoku::*;
+
+// Numbers strictly increase along the thermometer, starting from the bulb
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Thermometer {
+    // (row, col), the bulb first
+    pub cells: Vec<(usize, usize)>
+}
+
+impl Thermometer {
+    pub fn new(cells: Vec<(usize, usize)>) -> Thermometer {
+        Thermometer { cells }
+    }
+}
+
+impl Constraint for Thermometer {
+    // Two numbers are wrong if there aren't enough numbers between them for
+    // the cells between them
+    fn check(&self, sudoku: &Sudoku, wrong_numbers: &mut HashSet<(usize, usize)>) {
+        for (i, &(row, col)) in self.cells.iter().enumerate() {
+            let Some(low) = sudoku.check_position(row, col).value() else {
+                continue;
+            };
+
+            for (j, &(other_row, other_col)) in self.cells.iter().enumerate().skip(i + 1) {
+                let Some(high) = sudoku.check_position(other_row, other_col).value() else {
+                    continue;
+                };
+
+                if (high as usize) < low as usize + (j - i) {
+                    wrong_numbers.insert((row, col));
+                    wrong_numbers.insert((other_row, other_col));
+                }
+            }
+        }
+    }
+
+    // Keeps the numbers that leave room for the cells before and after it
+    fn prune(&self, sudoku: &Sudoku, row: usize, col: usize, candidates: &mut HashSet<u8>) {
+        let Some(k) = self.cells.iter().position(|&cell| cell == (row, col)) else {
+            return;
+        };
+
+        // too long for the numbers there are
+        let Some(mut high) = sudoku.digits().checked_sub(self.cells.len() - 1 - k) else {
+            candidates.clear();
+            return;
+        };
+        let mut low = k + 1;
+
+        for (i, &(i_row, i_col)) in self.cells.iter().enumerate() {
+            let Some(n) = sudoku.check_position(i_row, i_col).value() else {
+                continue;
+            };
+
+            if i < k {
+                low = low.max(n as usize + (k - i));
+            } else if i > k {
+                high = high.min((n as usize).saturating_sub(i - k));
+            }
+        }
+
+        candidates.retain(|&n| (low..=high).contains(&(n as usize)));
+    }
+
+    // Each number is smaller than the next one
+    fn encode(&self, sudoku: &Sudoku, cnf: &mut Cnf) {
+        let digits = sudoku.digits() as u8;
+
+        for pair in self.cells.windows(2) {
+            let ((row, col), (next_row, next_col)) = (pair[0], pair[1]);
+
+            for low in 1..=digits {
+                for high in 1..=low {
+                    cnf.forbid((row, col, low), (next_row, next_col, high));
+                }
+            }
+        }
+    }
+
+    fn shapes(&self, _size: usize) -> Vec<Shape> {
+        let center = |(row, col): (usize, usize)| (row as f32 + 0.5, col as f32 + 0.5);
+
+        let mut shapes: Vec<Shape> = self.cells.windows(2)
+            .map(|pair| Shape::Line {
+                from: center(pair[0]),
+                to: center(pair[1]),
+                width: 0.25,
+                ink: Ink::Clue
+            })
+            .collect();
+
+        if let Some(&bulb) = self.cells.first() {
+            shapes.push(Shape::Circle { center: center(bulb), radius: 0.35, filled: true, ink: Ink::Clue });
+        }
+
+        shapes
+    }
+
+    fn regenerate(&self) -> Regenerate {
+        Regenerate::Thermometer
+    }
+
+    fn name(&self) -> &'static str {
+        "thermo"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thermometer_violations() {
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(Thermometer::new(vec![(0, 0), (0, 1), (0, 2), (1, 2)]));
+
+        sudoku.insert_number(0, 0, 2);
+        sudoku.insert_number(1, 2, 5);
+        assert!(sudoku.check_rules().is_empty());
+
+        // no room for two numbers between 2 and 4
+        sudoku.insert_number(1, 2, 4);
+        assert_eq!(sudoku.check_rules(), HashSet::from([(0, 0), (1, 2)]));
+
+        // decreasing
+        sudoku.delete_number(1, 2);
+        sudoku.insert_number(0, 1, 1);
+        assert_eq!(sudoku.check_rules(), HashSet::from([(0, 0), (0, 1)]));
+    }
+
+    #[test]
+    fn thermometer_candidates() {
+        let mut sudoku = Sudoku::new(9);
+        sudoku.add_constraint(Thermometer::new(vec![(0, 0), (0, 1), (0, 2), (0, 3)]));
+        sudoku.insert_given(0, 3, 6);
+
+        assert_eq!(pruned(&sudoku, 0, 0), HashSet::from([1, 2, 3]));
+        assert_eq!(pruned(&sudoku, 0, 2), HashSet::from([3, 4, 5]));
+        assert_eq!(pruned(&sudoku, 4, 4), HashSet::from_iter(1..=9));
+    }
+
+    #[test]
+    fn thermometer_too_long() {
+        let path = std::path::Path::new("tests/longthermo");
+        assert!(matches!(Sudoku::from_file(path), Err(Error::LongThermometer { cells: 5, digits: 4 })));
+
+        let mut sudoku = Sudoku::new(4);
+        let thermometer = Thermometer::new(vec![(0, 0), (0, 1), (0, 2), (0, 3), (1, 3)]);
+        let mut candidates = HashSet::from_iter(1..=4);
+        thermometer.prune(&sudoku, 0, 0, &mut candidates);
+        assert!(candidates.is_empty());
+
+        sudoku.add_constraint(thermometer);
+        assert!(sudoku.solve().is_none());
+    }
+
+    #[test]
+    fn solve_thermometers() -> Result<(), anyhow::Error> {
+        let sudoku = solves("tests/thermo")?;
+        assert!(sudoku.constraints().iter().any(|c| c.name() == "thermo"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::hash_set::HashSet;
+
+use crate::sudoku::*;
+use crate::sudoku::logic::{combinations, Deduction, Technique};
+
+// Every step here takes out the numbers that would leave the puzzle with more
+// than one solution, so they're only right for puzzles known to have one. The
+// swaps they rely on can break the rules of a variant, so a sudoku with any
+// constraint doesn't get them either.
+
+// (row, col)
+type Cell = (usize, usize);
+// (row, col, number)
+type Candidate = (usize, usize, u8);
+
+impl Candidates {
+    fn numbers(&self, (row, col): Cell) -> Vec<u8> {
+        let mut numbers: Vec<u8> = self.get(row, col).iter().copied().collect();
+        numbers.sort_unstable();
+        numbers
+    }
+
+    // Rectangles with two cells (the floor) that only have x and y, and the
+    // other two (the roof) with both of them and maybe more. If the roof had
+    // nothing else x and y could be swapped in all four, so some of the rest
+    // has to be true. `kind` is the type of unique rectangle:
+    //
+    // 1. one of the roof cells only has x and y, so the other can't
+    // 2. both have the same one number more, which is in one of them
+    // 3. the numbers they have more make a naked subset with other cells of
+    //    a house they share
+    // 4. x only fits in the roof in a house they share, so they can't have y
+    pub(super) fn unique_rectangle(&mut self, sudoku: &Sudoku, kind: u8) -> Option<Deduction> {
+        if !sudoku.constraints().is_empty() {
+            return None;
+        }
+
+        for (floor, roof) in self.rectangles(sudoku) {
+            let [x, y] = self.numbers(floor[0])[..] else {
+                continue;
+            };
+            let extra = |cell| self.numbers(cell).into_iter().filter(|&n| n != x && n != y).collect::<Vec<_>>();
+            let (left, right) = (extra(roof[0]), extra(roof[1]));
+            let shared: Vec<usize> = sudoku.houses_of(roof[0].0, roof[0].1).iter()
+                .copied()
+                .filter(|house| sudoku.houses_of(roof[1].0, roof[1].1).contains(house))
+                .collect();
+
+            let (houses, targets) = match kind {
+                1 if left.is_empty() != right.is_empty() => {
+                    let (row, col) = if left.is_empty() { roof[1] } else { roof[0] };
+                    (Vec::new(), vec![(row, col, x), (row, col, y)])
+                }
+                2 if left.len() == 1 && left == right => {
+                    let targets = sudoku.iterate()
+                        .map(|(row, col, _)| (row, col))
+                        .filter(|&cell| {
+                            !roof.contains(&cell) && roof.iter().all(|&corner| sudoku.are_peers(cell, corner))
+                        })
+                        .map(|(row, col)| (row, col, left[0]))
+                        .collect();
+                    (Vec::new(), targets)
+                }
+                3 if !left.is_empty() && !right.is_empty() => {
+                    let extras: HashSet<u8> = left.iter().chain(&right).copied().collect();
+                    match self.virtual_subset(sudoku, &shared, &roof, &extras) {
+                        Some((house, targets)) => (vec![house], targets),
+                        None => continue
+                    }
+                }
+                4 if !left.is_empty() && !right.is_empty() => {
+                    let Some((house, n)) = shared.iter().find_map(|&house| {
+                        [(x, y), (y, x)].into_iter()
+                            .find(|&(n, _)| self.places(sudoku, &sudoku.houses()[house], n).as_deref() == Some(&roof[..]))
+                            .map(|(_, other)| (house, other))
+                    }) else {
+                        continue;
+                    };
+                    (vec![house], roof.iter().map(|&(row, col)| (row, col, n)).collect())
+                }
+                _ => continue
+            };
+
+            let eliminations = self.eliminate(targets);
+
+            if !eliminations.is_empty() {
+                return Some(Deduction {
+                    technique: Technique::UniqueRectangle { kind },
+                    houses,
+                    cells: vec![floor[0], floor[1], roof[0], roof[1]],
+                    digits: vec![x, y],
+                    eliminations,
+                    chain: Vec::new()
+                });
+            }
+        }
+
+        None
+    }
+
+    // The extra numbers of the roof, as if they were a cell, with other cells
+    // of a house that have as many numbers between them as there are cells
+    fn virtual_subset(
+        &self,
+        sudoku: &Sudoku,
+        shared: &[usize],
+        roof: &[Cell],
+        extras: &HashSet<u8>
+    ) -> Option<(usize, Vec<Candidate>)> {
+        for &house in shared {
+            let cells = &sudoku.houses()[house];
+            let open: Vec<Cell> = cells.iter().copied()
+                .filter(|cell| !roof.contains(cell) && !self.get(cell.0, cell.1).is_empty())
+                .collect();
+
+            for size in 1..open.len().min(4) {
+                for subset in combinations(&open, size) {
+                    let numbers: HashSet<u8> = subset.iter()
+                        .flat_map(|&(row, col)| self.get(row, col).iter().copied())
+                        .chain(extras.iter().copied())
+                        .collect();
+
+                    if numbers.len() != size + 1 {
+                        continue;
+                    }
+
+                    let targets: Vec<_> = open.iter()
+                        .filter(|cell| !subset.contains(cell))
+                        .flat_map(|&(row, col)| numbers.iter().map(move |&n| (row, col, n)))
+                        .filter(|&(row, col, n)| self.get(row, col).contains(&n))
+                        .collect();
+
+                    if !targets.is_empty() {
+                        return Some((house, targets));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    // The floor and roof of every rectangle of empty cells where swapping
+    // two numbers keeps every house right: each house with a corner has two
+    // of them, on the same side
+    fn rectangles(&self, sudoku: &Sudoku) -> Vec<([Cell; 2], [Cell; 2])> {
+        let size = sudoku.size();
+        let pairs: Vec<Cell> = sudoku.iterate()
+            .map(|(row, col, _)| (row, col))
+            .filter(|&cell| self.get(cell.0, cell.1).len() == 2)
+            .collect();
+        let mut rectangles = Vec::new();
+
+        for (i, &a) in pairs.iter().enumerate() {
+            for &b in &pairs[i + 1..] {
+                if self.numbers(a) != self.numbers(b) || (a.0 != b.0 && a.1 != b.1) {
+                    continue;
+                }
+
+                // the roof is another row (or column) of the same columns
+                let roofs = (0..size).filter_map(|line| match a.0 == b.0 {
+                    true if line != a.0 => Some([(line, a.1), (line, b.1)]),
+                    false if line != a.1 => Some([(a.0, line), (b.0, line)]),
+                    _ => None
+                });
+
+                for roof in roofs {
+                    let corners = [a, b, roof[0], roof[1]];
+                    let has_pair = |&(row, col): &Cell| {
+                        self.numbers(a).iter().all(|n| self.get(row, col).contains(n))
+                    };
+
+                    if roof.iter().all(has_pair) && is_deadly(sudoku, corners) {
+                        rectangles.push(([a, b], roof));
+                    }
+                }
+            }
+        }
+
+        rectangles
+    }
+
+    // When every empty cell has two numbers but one with three, the one in
+    // three places of its houses goes there: without it every number would
+    // have two places in each house, which always makes two solutions
+    pub(super) fn bug_plus_one(&mut self, sudoku: &Sudoku) -> Option<Deduction> {
+        if !sudoku.constraints().is_empty() {
+            return None;
+        }
+
+        let open: Vec<Cell> = sudoku.iterate()
+            .map(|(row, col, _)| (row, col))
+            .filter(|&(row, col)| !self.get(row, col).is_empty())
+            .collect();
+
+        let [(row, col)] = open.iter().copied().filter(|&cell| self.get(cell.0, cell.1).len() != 2).collect::<Vec<_>>()[..] else {
+            return None;
+        };
+
+        if self.get(row, col).len() != 3 {
+            return None;
+        }
+
+        let (house, n) = sudoku.houses_of(row, col).iter().find_map(|&house| {
+            self.numbers((row, col)).into_iter()
+                .find(|&n| self.places(sudoku, &sudoku.houses()[house], n).is_some_and(|places| places.len() == 3))
+                .map(|n| (house, n))
+        })?;
+
+        let targets: Vec<_> = self.numbers((row, col)).into_iter()
+            .filter(|&m| m != n)
+            .map(|m| (row, col, m))
+            .collect();
+        let eliminations = self.eliminate(targets);
+
+        Some(Deduction {
+            technique: Technique::BugPlusOne,
+            houses: vec![house],
+            cells: vec![(row, col)],
+            digits: vec![n],
+            eliminations,
+            chain: Vec::new()
+        })
+    }
+}
+
+// The corners go a, b on one side and c, d on the other, with a across from d
+fn is_deadly(sudoku: &Sudoku, corners: [Cell; 4]) -> bool {
+    let across = [3, 2, 1, 0];
+
+    corners.iter().enumerate().all(|(i, &(row, col))| {
+        sudoku.houses_of(row, col).iter().all(|house| {
+            let in_house: Vec<usize> = (0..4)
+                .filter(|&j| sudoku.houses_of(corners[j].0, corners[j].1).contains(house))
+                .collect();
+
+            in_house.len() == 2 && !in_house.contains(&across[i])
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_rectangles() {
+        let sudoku = Sudoku::new(9);
+        let floor: [(Cell, &[u8]); 2] = [((0, 0), &[1, 2]), ((0, 3), &[1, 2])];
+
+        // type 1
+        let mut candidates = Candidates::with_cells(&sudoku, &[floor[0], floor[1], ((1, 0), &[1, 2]), ((1, 3), &[1, 2, 5, 7])]);
+        let deduction = candidates.unique_rectangle(&sudoku, 1).unwrap();
+        assert_eq!(deduction.technique, Technique::UniqueRectangle { kind: 1 });
+        assert_eq!(deduction.eliminations, vec![(1, 3, 1), (1, 3, 2)]);
+
+        // type 2, the 5 is out of the rest of the row
+        let mut candidates = Candidates::with_cells(&sudoku, &[floor[0], floor[1], ((1, 0), &[1, 2, 5]), ((1, 3), &[1, 2, 5])]);
+        let deduction = candidates.unique_rectangle(&sudoku, 2).unwrap();
+        assert_eq!(deduction.eliminations.len(), 7);
+        assert!(deduction.eliminations.iter().all(|&(row, _, n)| row == 1 && n == 5));
+
+        // type 3, with a cell of 5 and 6 making a pair
+        let mut candidates = Candidates::with_cells(&sudoku, &[
+            floor[0], floor[1], ((1, 0), &[1, 2, 5]), ((1, 3), &[1, 2, 6]), ((1, 8), &[5, 6])
+        ]);
+        let deduction = candidates.unique_rectangle(&sudoku, 3).unwrap();
+        assert_eq!(deduction.houses, vec![1]);
+        assert_eq!(deduction.eliminations.len(), 12);
+
+        // type 4, 1 only fits in the roof in its row
+        let mut candidates = Candidates::with_cells(&sudoku, &[floor[0], floor[1], ((1, 0), &[1, 2, 5]), ((1, 3), &[1, 2, 6])]);
+        let targets: Vec<_> = (0..9).filter(|col| ![0, 3].contains(col)).map(|col| (1, col, 1)).collect();
+        candidates.eliminate(targets);
+        let deduction = candidates.unique_rectangle(&sudoku, 4).unwrap();
+        assert_eq!(deduction.eliminations, vec![(1, 0, 2), (1, 3, 2)]);
+
+        // across four boxes it isn't deadly
+        let mut candidates = Candidates::with_cells(&sudoku, &[
+            ((0, 0), &[1, 2]), ((0, 4), &[1, 2]), ((4, 0), &[1, 2]), ((4, 4), &[1, 2, 3])
+        ]);
+        assert_eq!(candidates.unique_rectangle(&sudoku, 1), None);
+    }
+
+    #[test]
+    fn bug_plus_one() {
+        // the numbers of a solution and of the same one with 1 and 2, and 3
+        // and 4, swapped, so each is in two places of every house, and a 3
+        // more in the corner
+        let sudoku = Sudoku::new(4);
+        let solution = [[1, 2, 3, 4], [3, 4, 1, 2], [2, 1, 4, 3], [4, 3, 2, 1]];
+        let mut cells = Vec::new();
+        for (row, line) in solution.iter().enumerate() {
+            for (col, &n) in line.iter().enumerate() {
+                cells.push(((row, col), if n <= 2 { &[1, 2][..] } else { &[3, 4][..] }));
+            }
+        }
+        cells[0].1 = &[1, 2, 3];
+
+        let mut candidates = Candidates::with_cells(&sudoku, &cells);
+        let deduction = candidates.bug_plus_one(&sudoku).unwrap();
+        assert_eq!(deduction.technique, Technique::BugPlusOne);
+        assert_eq!(deduction.digits, vec![3]);
+        assert_eq!(deduction.eliminations, vec![(0, 0, 1), (0, 0, 2)]);
+
+        // with a constraint the swaps might not work
+        let mut sudoku = sudoku;
+        sudoku.add_constraint(Chess::AntiKing);
+        assert_eq!(Candidates::with_cells(&sudoku, &cells).bug_plus_one(&sudoku), None);
+    }
+}